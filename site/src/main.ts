@@ -1,5 +1,5 @@
 import './style.css'
-import {GarnishScript} from "browser_garnish";
+import {GarnishScript, SourceFormatOptions, format_source} from "browser_garnish";
 
 let script = new GarnishScript("main", "");
 
@@ -14,6 +14,15 @@ sourceArea.addEventListener("keydown", (e) => {
         let area = e.target as HTMLTextAreaElement;
         area.value = area.value + "  ";
         script.set_text(area.value);
+    } else if (e.code === "KeyS" && (e.ctrlKey || e.metaKey)) {
+        e.preventDefault();
+        let area = e.target as HTMLTextAreaElement;
+        try {
+            area.value = format_source(area.value, new SourceFormatOptions());
+            script.set_text(area.value);
+        } catch (error) {
+            console.error(error);
+        }
     }
 });
 
@@ -0,0 +1,265 @@
+use crate::compile::{compile_annotated_tokens, compile_tokens_into_data, identifiers_used, BUILT_IN_ANNOTATIONS};
+use crate::context::BrowserContext;
+use garnish_lang::compiler::lex::LexerToken;
+use garnish_lang::simple::{SimpleData, SimpleGarnishData};
+use garnish_lang_annotations_collector::{PartParser, Sink, TokenBlock};
+
+pub type AnnotationCallback = Box<dyn Fn(&TokenBlock, &mut AnnotationScope) -> Result<(), String>>;
+
+/// Handler for an annotation not built in to the compiler.
+///
+/// Parts are collected the same way as built in annotations
+/// and the callback is called with the collected block during compilation.
+pub struct AnnotationHandler {
+    annotation: String,
+    parts: Vec<PartParser>,
+    callback: AnnotationCallback,
+}
+
+impl AnnotationHandler {
+    pub fn new<F>(annotation: &str, callback: F) -> Self
+    where
+        F: Fn(&TokenBlock, &mut AnnotationScope) -> Result<(), String> + 'static,
+    {
+        AnnotationHandler {
+            annotation: annotation.to_string(),
+            parts: vec![],
+            callback: Box::new(callback),
+        }
+    }
+
+    pub fn part(mut self, part: PartParser) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    pub fn annotation(&self) -> &String {
+        &self.annotation
+    }
+
+    fn sink(&self) -> Sink {
+        self.parts
+            .iter()
+            .fold(Sink::new(&self.annotation), |sink, part| sink.part(part.clone()))
+    }
+}
+
+#[derive(Default)]
+pub struct AnnotationRegistry {
+    handlers: Vec<AnnotationHandler>,
+}
+
+impl AnnotationRegistry {
+    pub fn new() -> Self {
+        AnnotationRegistry { handlers: vec![] }
+    }
+
+    /// Add a handler, replacing any existing handler for the same annotation.
+    /// Built in annotations can't be replaced.
+    pub fn register(&mut self, handler: AnnotationHandler) -> Result<(), String> {
+        if BUILT_IN_ANNOTATIONS.contains(&handler.annotation.as_str()) {
+            return Err(format!("`{}` is a built in annotation and can't be replaced", handler.annotation));
+        }
+
+        self.handlers.retain(|h| h.annotation != handler.annotation);
+        self.handlers.push(handler);
+        Ok(())
+    }
+
+    pub fn get(&self, annotation: &str) -> Option<&AnnotationHandler> {
+        self.handlers.iter().find(|h| h.annotation == annotation)
+    }
+
+    pub fn sinks(&self) -> Vec<Sink> {
+        self.handlers.iter().map(AnnotationHandler::sink).collect()
+    }
+
+    pub fn handle(
+        &self,
+        block: &TokenBlock,
+        data: &mut SimpleGarnishData,
+        context: &mut BrowserContext,
+    ) -> Result<(), String> {
+        let handler = self
+            .get(block.annotation_text())
+            .ok_or(format!("No handler registered for {}", block.annotation_text()))?;
+
+        let mut scope = AnnotationScope {
+            data,
+            context,
+            registry: self,
+        };
+
+        (handler.callback)(block, &mut scope)
+            .map_err(|e| format!("Error handling {}: {}", block.annotation_text(), e))
+    }
+}
+
+/// Access to the compilation in progress, given to annotation callbacks.
+pub struct AnnotationScope<'a> {
+    data: &'a mut SimpleGarnishData,
+    context: &'a mut BrowserContext,
+    registry: &'a AnnotationRegistry,
+}
+
+impl<'a> AnnotationScope<'a> {
+    pub fn context(&self) -> &BrowserContext {
+        self.context
+    }
+
+    /// Compile tokens into an expression that can be referenced by name, like a `@Def`.
+    pub fn add_expression(&mut self, name: &str, tokens: &[LexerToken]) -> Result<(), String> {
+        compile_tokens_into_data(&Vec::from(tokens), name, self.data, self.context, self.registry)
+    }
+
+    /// Compile tokens into an expression that isn't mapped to a name, returning its expression index.
+    pub fn compile_expression(&mut self, tokens: &[LexerToken]) -> Result<usize, String> {
+        compile_annotated_tokens(&Vec::from(tokens), self.data, self.context, self.registry)
+    }
+
+    /// Add a constant value that doesn't reference other data.
+    pub fn add_constant(&mut self, name: &str, value: SimpleData) {
+        self.context.add_symbol_data(name, value);
+    }
+
+    /// Compile tokens into an expression evaluated as a constant once every source is compiled, like a `@Const`.
+    pub fn add_evaluated_constant(&mut self, name: &str, tokens: &[LexerToken]) -> Result<(), String> {
+        let expression_index = self.compile_expression(tokens)?;
+        self.context.add_pending_constant(name, expression_index, identifiers_used(tokens));
+        Ok(())
+    }
+
+    pub fn add_metadata(&mut self, key: &str, value: &str) {
+        self.context.add_metadata(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotations::{AnnotationHandler, AnnotationScope};
+    use crate::compile::{definition_expression, definition_name};
+    use crate::script::GarnishScript;
+    use garnish_lang::compiler::lex::{LexerToken, TokenType};
+    use garnish_lang::simple::{SimpleData, SimpleNumber};
+    use garnish_lang_annotations_collector::{PartBehavior, PartParser};
+
+    type NamedExpressionCallback = fn(&str, &[LexerToken], &mut AnnotationScope) -> Result<(), String>;
+
+    // handler for `@Annotation name { expression }`
+    fn named_expression(annotation: &str, callback: NamedExpressionCallback) -> AnnotationHandler {
+        AnnotationHandler::new(annotation, move |block, scope| {
+            let name = definition_name(block)?.get_text().clone();
+            let expression = definition_expression(block)?;
+            callback(&name, expression, scope)
+        })
+        .part(PartParser::new(PartBehavior::TokenCount(1)))
+        .part(PartParser::new(PartBehavior::UntilToken(TokenType::EndExpression)))
+    }
+
+    #[test]
+    fn add_expression() {
+        let mut script = GarnishScript::new("main".to_string(), "@Function double { $ * 2 }\n\ndouble ~ 21".to_string());
+        script
+            .register_annotation(named_expression("@Function", |name, tokens, scope| scope.add_expression(name, tokens)))
+            .unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_error(), None);
+        assert_eq!(script.get_execution_result(0), Some("42".to_string()));
+    }
+
+    #[test]
+    fn add_constants() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Setting Width { 40 * 2 }\n\nWidth + Height".to_string(),
+        );
+        script.register_annotation(named_expression("@Setting", |name, tokens, scope| {
+            scope.add_evaluated_constant(name, tokens)?;
+            scope.add_constant("Height", SimpleData::Number(SimpleNumber::Integer(20)));
+            Ok(())
+        }))
+        .unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_execution_result(0), Some("100".to_string()));
+    }
+
+    #[test]
+    fn evaluated_constant_uses_later_sources() {
+        let mut script = GarnishScript::new("main".to_string(), "@Setting Width { half ~ 80 }\n\nWidth".to_string());
+        script.include("helpers".to_string(), "@Def half { $ / 2 }".to_string());
+        script
+            .register_annotation(named_expression("@Setting", |name, tokens, scope| {
+                scope.add_evaluated_constant(name, tokens)
+            }))
+            .unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_error(), None);
+        assert_eq!(script.get_execution_result(0), Some("40".to_string()));
+    }
+
+    #[test]
+    fn built_in_not_replaced() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def five { 5 }\n\nfive ~ ()".to_string());
+
+        for annotation in ["@Def", "@Const", "@Test", "@Doc"] {
+            assert_eq!(
+                script.register_annotation(AnnotationHandler::new(annotation, |_, _| Ok(()))),
+                Err(format!("`{}` is a built in annotation and can't be replaced", annotation))
+            );
+        }
+
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_execution_result(0), Some("5".to_string()));
+    }
+
+    #[test]
+    fn add_metadata() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Author \"Someone\"\n\n@Author \"Someone Else\"\n\n5".to_string(),
+        );
+        script.register_annotation(
+            AnnotationHandler::new("@Author", |block, scope| {
+                let name = block.parts()[0]
+                    .iter()
+                    .find(|t| t.get_token_type() == TokenType::CharList)
+                    .ok_or("Expected name")?;
+                scope.add_metadata("author", name.get_text().trim_matches('"'));
+                Ok(())
+            })
+            .part(PartParser::new(PartBehavior::TokenCount(1))),
+        )
+        .unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_metadata("author".to_string()), vec!["Someone", "Someone Else"]);
+        assert_eq!(script.get_execution_result(0), Some("5".to_string()));
+
+        script.compile();
+        assert_eq!(script.get_metadata("author".to_string()).len(), 2);
+    }
+
+    #[test]
+    fn callback_error() {
+        let mut script = GarnishScript::new("main".to_string(), "@Fail { 5 }\n\n5".to_string());
+        script.register_annotation(
+            AnnotationHandler::new("@Fail", |_, _| Err("always fails".to_string()))
+                .part(PartParser::new(PartBehavior::UntilToken(TokenType::EndExpression))),
+        )
+        .unwrap();
+        script.compile();
+
+        assert_eq!(
+            script.get_error(),
+            Some("Error compiling main: Error handling @Fail: always fails".to_string())
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::annotations::AnnotationRegistry;
+use crate::compile::{collect_annotations, definition_expression, definition_name};
+use crate::tokens::end_position;
+use garnish_lang::compiler::lex::{lex, LexerToken};
+use garnish_lang::compiler::parse::{parse, ParseNode};
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Parse tree of source text as JSON.
+///
+/// The root expression is under `root`, each `@Def`, `@Test` and `@Const` block is listed
+/// under `definitions` with its parsed expression.
+/// Each node has its `definition`, `secondary_definition`, `token`, `left` and `right` children,
+/// and a `span` covering the tokens of the node and all its children.
+/// Use `GarnishScript::get_ast` for sources with registered annotations.
+#[wasm_bindgen]
+pub fn get_ast(source: String) -> Result<String, String> {
+    annotated_ast(&source, &AnnotationRegistry::new())
+}
+
+/// Parse tree of source text, leaving registered annotation blocks out of the root expression.
+pub fn annotated_ast(source: &str, registry: &AnnotationRegistry) -> Result<String, String> {
+    let tokens = lex(source).map_err(|e| e.get_message().clone())?;
+    let blocks = collect_annotations(&tokens, registry)?;
+
+    let (root_blocks, annotation_blocks): (Vec<_>, Vec<_>) = blocks
+        .iter()
+        .partition(|block| block.annotation_text().is_empty());
+
+    let root_tokens: Vec<LexerToken> = root_blocks
+        .into_iter()
+        .flat_map(|block| block.tokens().clone())
+        .collect();
+
+    let definitions = annotation_blocks
+        .into_iter()
+        .filter_map(|block| {
+            // @Doc blocks don't have a name or expression
+            let name = definition_name(block).ok()?;
+            let expression = definition_expression(block).ok()?;
+            Some((block.annotation_text(), name, expression))
+        })
+        .map(|(annotation, name, expression)| {
+            Ok(json!({
+                "annotation": annotation,
+                "name": name.get_text(),
+                "root": parse_tree(expression)?,
+            }))
+        })
+        .collect::<Result<Vec<Value>, String>>()?;
+
+    let ast = json!({
+        "root": parse_tree(&root_tokens)?,
+        "definitions": definitions,
+    });
+
+    Ok(ast.to_string())
+}
+
+fn parse_tree(tokens: &[LexerToken]) -> Result<Value, String> {
+    let result = parse(&Vec::from(tokens)).map_err(|e| e.get_message().clone())?;
+
+    Ok(node_json(result.get_root(), result.get_nodes()))
+}
+
+fn node_json(index: usize, nodes: &[ParseNode]) -> Value {
+    let node = match nodes.get(index) {
+        Some(node) => node,
+        None => return Value::Null,
+    };
+
+    let child = |child: Option<usize>| child.map(|i| node_json(i, nodes)).unwrap_or(Value::Null);
+    let token = node.get_lex_token();
+    let (start, end) = span(index, nodes);
+
+    json!({
+        "definition": format!("{:?}", node.get_definition()),
+        "secondary_definition": format!("{:?}", node.get_secondary_definition()),
+        "token": {
+            "type": format!("{:?}", token.get_token_type()),
+            "text": token.get_text(),
+        },
+        "span": {
+            "start": { "line": start.0, "column": start.1 },
+            "end": { "line": end.0, "column": end.1 },
+        },
+        "left": child(node.get_left()),
+        "right": child(node.get_right()),
+    })
+}
+
+// from the start of the earliest token to the end of the latest token in the subtree
+// closing tokens, like the end of a group, aren't part of the tree so aren't included
+fn span(index: usize, nodes: &[ParseNode]) -> ((usize, usize), (usize, usize)) {
+    let node = &nodes[index];
+    let token = node.get_lex_token();
+    let own = ((token.get_line(), token.get_column()), end_position(&token));
+
+    [node.get_left(), node.get_right()]
+        .iter()
+        .flatten()
+        .filter(|i| **i < nodes.len())
+        .map(|i| span(*i, nodes))
+        .fold(own, |(start, end), (child_start, child_end)| {
+            (start.min(child_start), end.max(child_end))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::get_ast;
+    use serde_json::{json, Value};
+
+    fn ast(source: &str) -> Value {
+        serde_json::from_str(&get_ast(source.to_string()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn binary_operation() {
+        let tree = ast("5 + 10");
+        let root = &tree["root"];
+
+        assert_eq!(root["definition"], "Addition");
+        assert_eq!(root["token"], json!({ "type": "PlusSign", "text": "+" }));
+        assert_eq!(root["left"]["definition"], "Number");
+        assert_eq!(root["left"]["token"]["text"], "5");
+        assert_eq!(root["right"]["token"]["text"], "10");
+        assert_eq!(root["right"]["left"], Value::Null);
+        assert_eq!(
+            root["span"],
+            json!({ "start": { "line": 0, "column": 0 }, "end": { "line": 0, "column": 6 } })
+        );
+    }
+
+    #[test]
+    fn definitions() {
+        let tree = ast("@Def add_5 { $ + 5 }\n\nadd_5 ~ 10");
+
+        assert_eq!(tree["root"]["definition"], "Apply");
+        assert_eq!(tree["definitions"][0]["annotation"], "@Def");
+        assert_eq!(tree["definitions"][0]["name"], "add_5");
+        assert_eq!(tree["definitions"][0]["root"]["definition"], "Addition");
+        assert_eq!(tree["definitions"][0]["root"]["span"]["start"], json!({ "line": 0, "column": 13 }));
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!(get_ast("5 +".to_string()).is_err());
+        assert!(get_ast("\"open".to_string()).is_err());
+    }
+}
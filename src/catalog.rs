@@ -0,0 +1,289 @@
+use crate::library::NativeFunction;
+use garnish_lang::simple::SimpleData;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// What built-ins a script may use, each level allowing everything the previous one does.
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Capabilities {
+    /// Functions of their input only, such as `Str::` and `Math::`.
+    Pure,
+    /// Also `Random::` and `Time::`, with the generator seeded with 0 and the clock fixed at 0
+    /// unless set on the script, so every run gives the same result.
+    Deterministic,
+    /// Also the current time, time seeded randomness and annotations handled by the host.
+    Full,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemberKind {
+    Function,
+    Constant,
+}
+
+#[derive(Debug, Clone)]
+pub enum MemberValue {
+    Function(NativeFunction),
+    Constant(SimpleData),
+}
+
+/// Function or constant of a built-in module, resolved by its full name, `Math::abs`.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Member {
+    name: String,
+    signature: String,
+    description: String,
+    example: String,
+    value: MemberValue,
+}
+
+#[wasm_bindgen]
+impl Member {
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_kind(&self) -> MemberKind {
+        match self.value {
+            MemberValue::Function(_) => MemberKind::Function,
+            MemberValue::Constant(_) => MemberKind::Constant,
+        }
+    }
+
+    /// How the member is used, `Str::split ~ (text, separator)`.
+    pub fn get_signature(&self) -> String {
+        self.signature.clone()
+    }
+
+    pub fn get_description(&self) -> String {
+        self.description.clone()
+    }
+
+    /// Garnish expression using the member.
+    pub fn get_example(&self) -> String {
+        self.example.clone()
+    }
+}
+
+impl Member {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn value(&self) -> &MemberValue {
+        &self.value
+    }
+}
+
+/// Namespace of built-in functions and constants.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Module {
+    name: String,
+    description: String,
+    members: Vec<Member>,
+    enabled: bool,
+    requires: Capabilities,
+}
+
+#[wasm_bindgen]
+impl Module {
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_description(&self) -> String {
+        self.description.clone()
+    }
+
+    pub fn get_members(&self) -> Vec<Member> {
+        self.members.clone()
+    }
+
+    /// Members of disabled modules don't resolve and aren't offered as completions.
+    pub fn get_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Capabilities a script needs to use the module's members.
+    pub fn get_requires(&self) -> Capabilities {
+        self.requires
+    }
+}
+
+impl Module {
+    pub fn new(name: &str, description: &str) -> Self {
+        Module {
+            name: name.to_string(),
+            description: description.to_string(),
+            members: vec![],
+            enabled: true,
+            requires: Capabilities::Pure,
+        }
+    }
+
+    pub fn requires(mut self, capabilities: Capabilities) -> Self {
+        self.requires = capabilities;
+        self
+    }
+
+    /// Add a function named `<module>::<name>`.
+    pub fn function(
+        mut self,
+        name: &str,
+        signature: &str,
+        description: &str,
+        example: &str,
+        function: NativeFunction,
+    ) -> Self {
+        self.members.push(Member {
+            name: format!("{}::{}", self.name, name),
+            signature: signature.to_string(),
+            description: description.to_string(),
+            example: example.to_string(),
+            value: MemberValue::Function(function),
+        });
+        self
+    }
+
+    /// Add a constant named `<module>::<name>`, its name is also its signature and example.
+    pub fn constant(mut self, name: &str, description: &str, value: SimpleData) -> Self {
+        let name = format!("{}::{}", self.name, name);
+        self.members.push(Member {
+            signature: name.clone(),
+            description: description.to_string(),
+            example: name.clone(),
+            name,
+            value: MemberValue::Constant(value),
+        });
+        self
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::{Capabilities, MemberKind};
+    use crate::lint::Severity;
+    use crate::script::GarnishScript;
+
+    fn script_with(source: &str, capabilities: Capabilities) -> GarnishScript {
+        let mut script = GarnishScript::with_capabilities("main".to_string(), source.to_string(), capabilities);
+        script.compile();
+        script.execute();
+        script
+    }
+
+    #[test]
+    fn examples_run() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+
+        for member in script.get_modules().iter().flat_map(|module| module.get_members()) {
+            let mut example = GarnishScript::new("main".to_string(), member.get_example());
+            example.set_random_seed(Some(0));
+            example.set_fixed_time(Some(0.0));
+            example.compile();
+            example.execute();
+
+            assert_eq!(example.get_error(), None, "{}", member.get_name());
+            assert_ne!(example.get_execution_result(0), Some("()".to_string()), "{}", member.get_name());
+        }
+    }
+
+    #[test]
+    fn members_named_by_module() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let math = script.get_modules().into_iter().find(|module| module.get_name() == "Math").unwrap();
+        let pi = math.get_members().into_iter().find(|member| member.get_name() == "Math::PI").unwrap();
+
+        assert_eq!(pi.get_kind(), MemberKind::Constant);
+        assert!(math.get_enabled());
+    }
+
+    #[test]
+    fn disabled_module_unresolved() {
+        let mut script = GarnishScript::new("main".to_string(), "Str::length ~ \"abc\", Random::int ~ (1, 6)".to_string());
+        script.set_module_enabled("Random".to_string(), false).unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_execution_result(0), Some("3, ()".to_string()));
+        assert!(!script.get_modules().iter().find(|module| module.get_name() == "Random").unwrap().get_enabled());
+        assert!(script
+            .get_diagnostics()
+            .iter()
+            .any(|diagnostic| diagnostic.get_message().contains("Random::int")));
+    }
+
+    #[test]
+    fn unknown_module() {
+        let mut script = GarnishScript::new("main".to_string(), "".to_string());
+
+        assert_eq!(
+            script.set_module_enabled("Nope".to_string(), false),
+            Err("No module named `Nope`".to_string())
+        );
+    }
+
+    #[test]
+    fn pure_capabilities() {
+        let script = script_with("Str::upper ~ \"abc\", Math::abs ~ (0 - 2)", Capabilities::Pure);
+        assert_eq!(script.get_execution_result(0), Some("\"ABC\", 2".to_string()));
+
+        let message = "`Random::int` isn't allowed with Pure capabilities, the Random module requires Deterministic";
+        let script = script_with("Random::int ~ (1, 6)", Capabilities::Pure);
+        assert_eq!(script.get_error(), Some(message.to_string()));
+
+        let diagnostics = script.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].get_severity(), Severity::Error);
+        assert_eq!(diagnostics[0].get_message(), message);
+    }
+
+    #[test]
+    fn disallowed_not_listed() {
+        let script = GarnishScript::with_capabilities("main".to_string(), "".to_string(), Capabilities::Pure);
+
+        assert!(script.completions("Time::".to_string(), 0, 6).is_empty());
+        assert!(script.hover("Time::now".to_string(), 0, 2).is_none());
+        assert_eq!(script.completions("Str::len".to_string(), 0, 8).len(), 1);
+    }
+
+    #[test]
+    fn deterministic_capabilities() {
+        let source = "Random::int ~ (1, 1000000), Time::now ~ ()";
+        let first = script_with(source, Capabilities::Deterministic).get_execution_result(0);
+
+        assert_eq!(first, script_with(source, Capabilities::Deterministic).get_execution_result(0));
+        assert!(first.unwrap().ends_with(", 0"));
+
+        let mut script = GarnishScript::with_capabilities("main".to_string(), "Time::now ~ ()".to_string(), Capabilities::Deterministic);
+        script.set_fixed_time(Some(5.0));
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_execution_result(0), Some("5".to_string()));
+    }
+
+    #[test]
+    fn full_by_default() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let time = script.get_modules().into_iter().find(|module| module.get_name() == "Time").unwrap();
+
+        assert_eq!(script.get_capabilities(), Capabilities::Full);
+        assert_eq!(time.get_requires(), Capabilities::Deterministic);
+        assert_ne!(script_with("Time::now ~ ()", Capabilities::Full).get_execution_result(0), Some("0".to_string()));
+    }
+}
@@ -1,16 +1,28 @@
+use crate::annotations::AnnotationRegistry;
 use crate::context::BrowserContext;
+use crate::display::FormatOptions;
+use crate::execution::run_expression;
 use crate::script::SourceDetails;
+use crate::tokens::is_resolved_identifier;
 use garnish_lang::compiler::build::build_with_data;
 use garnish_lang::compiler::lex::{lex, LexerToken, TokenType};
 use garnish_lang::compiler::parse::parse;
-use garnish_lang::simple::SimpleGarnishData;
+use garnish_lang::simple::{parse_char_list, SimpleGarnishData};
 use garnish_lang::GarnishData;
 use garnish_lang_annotations_collector::{Collector, PartBehavior, PartParser, Sink, TokenBlock};
+use std::collections::HashMap;
+
+pub const DEF_ANNOTATION: &str = "@Def";
+pub const TEST_ANNOTATION: &str = "@Test";
+pub const CONST_ANNOTATION: &str = "@Const";
+pub const DOC_ANNOTATION: &str = "@Doc";
+pub const BUILT_IN_ANNOTATIONS: [&str; 4] = [DEF_ANNOTATION, TEST_ANNOTATION, CONST_ANNOTATION, DOC_ANNOTATION];
 
 pub fn compile_source_into_data(
     source: &SourceDetails,
     data: &mut SimpleGarnishData,
     context: &mut BrowserContext,
+    registry: &AnnotationRegistry,
 ) -> Result<(), String> {
     let tokens = match lex(source.text()) {
         Ok(tokens) => tokens,
@@ -19,24 +31,36 @@ pub fn compile_source_into_data(
         }
     };
 
-    compile_tokens_into_data(&tokens, source.name(), data, context)
+    compile_tokens_into_data(&tokens, source.name(), data, context, registry)
 }
 
-fn compile_tokens_into_data(
+pub fn compile_tokens_into_data(
     tokens: &Vec<LexerToken>,
-    name: &String,
+    name: &str,
     data: &mut SimpleGarnishData,
     context: &mut BrowserContext,
+    registry: &AnnotationRegistry,
 ) -> Result<(), String> {
-    let collector = Collector::new(vec![Sink::new("@Def")
-        .part(PartParser::new(PartBehavior::TokenCount(1)))
-        .part(PartParser::new(PartBehavior::UntilToken(
-            TokenType::EndExpression,
-        )))]);
+    let root_point = compile_annotated_tokens(tokens, data, context, registry)?;
+    context.add_expression_mapping(name, root_point);
+
+    Ok(())
+}
+
+/// Compile root tokens and any annotation blocks within them.
+/// Returns the expression index of the root expression.
+pub fn compile_annotated_tokens(
+    tokens: &Vec<LexerToken>,
+    data: &mut SimpleGarnishData,
+    context: &mut BrowserContext,
+    registry: &AnnotationRegistry,
+) -> Result<usize, String> {
+    let collection = collect_annotations(tokens, registry)?;
+    let documentation = definition_documentation(&collection);
 
-    let collection = collector.collect_tokens(&tokens)?;
-    let (root_blocks, def_blocks): (Vec<TokenBlock>, Vec<TokenBlock>) = collection
+    let (root_blocks, annotation_blocks): (Vec<TokenBlock>, Vec<TokenBlock>) = collection
         .into_iter()
+        .filter(|block| block.annotation_text() != DOC_ANNOTATION)
         .partition(|block| block.annotation_text().is_empty());
 
     let root_tokens: Vec<LexerToken> = root_blocks
@@ -44,48 +68,461 @@ fn compile_tokens_into_data(
         .flat_map(|block| block.tokens_owned())
         .collect();
 
-    let parse_result = parse(&root_tokens).or_else(|e| Err(e.get_message().clone()))?;
+    let parse_result = parse(&root_tokens).map_err(|e| e.get_message().clone())?;
 
     let root_point = data.get_jump_table_len();
-    context.add_expression_mapping(&name, root_point);
 
     build_with_data(
         parse_result.get_root(),
         parse_result.get_nodes().clone(),
         data,
-    ).or_else(|e| Err(e.get_message().clone()))?;
-
-    for def in def_blocks {
-        let name_part = def
-            .parts()
-            .get(0)
-            .ok_or("No name part found for @Def annotation")?;
-        let identifier = name_part
-            .iter()
-            .find(|t| t.get_token_type() == TokenType::Identifier)
-            .ok_or("Expected identifier for @Def name")?;
-        let expression_part = def
-            .parts()
-            .get(1)
-            .ok_or("No expression found for @Def annotation")?;
-        let (start, _) = expression_part
-            .iter()
-            .enumerate()
-            .find(|(_, token)| token.get_token_type() == TokenType::StartExpression)
-            .ok_or("Expected expression after identifier for @Def annotation")?;
-        let (end, _) = expression_part
-            .iter()
-            .enumerate()
-            .rev()
-            .find(|(_, token)| token.get_token_type() == TokenType::EndExpression)
-            .ok_or("Expected expression after identifier for @Def annotation")?;
+    ).map_err(|e| e.get_message().clone())?;
+
+    for block in annotation_blocks {
+        match block.annotation_text().as_str() {
+            TEST_ANNOTATION => {
+                let identifier = definition_name(&block)?;
+                let expression = Vec::from(definition_expression(&block)?);
+
+                // tests aren't mapped by name, so can't be referenced by other expressions
+                let point = compile_annotated_tokens(&expression, data, context, registry)?;
+                context.add_test(identifier.get_text(), point);
+            }
+            DEF_ANNOTATION => {
+                let identifier = definition_name(&block)?;
+                let parameters = definition_parameters(&block)?;
+                let expression = bind_parameters(&block, definition_expression(&block)?, &parameters)?;
 
-        compile_tokens_into_data(&Vec::from(&expression_part[(start + 1)..end]), identifier.get_text(), data, context)?;
+                compile_tokens_into_data(&expression, identifier.get_text(), data, context, registry)?;
+
+                if let Some(text) = documentation.get(identifier.get_text()) {
+                    context.add_documentation(identifier.get_text(), text);
+                }
+            }
+            // evaluated by evaluate_constants once every source is compiled,
+            // so they can use definitions from anywhere in the sources
+            CONST_ANNOTATION => {
+                let identifier = definition_name(&block)?;
+                let expression = Vec::from(definition_expression(&block)?);
+
+                let point = compile_annotated_tokens(&expression, data, context, registry)?;
+                context.add_pending_constant(identifier.get_text(), point, identifiers_used(&expression));
+            }
+            _ => registry.handle(&block, data, context)?,
+        }
     }
 
     for value in data.get_data().symbol_to_name().values() {
         context.add_symbol_name(value);
     }
 
+    Ok(root_point)
+}
+
+/// Evaluate the `@Const` blocks of all compiled sources, each with the given instruction limit.
+///
+/// A constant is evaluated after the constants its expression names directly,
+/// otherwise in the order they were compiled. Constants that name each other are an error.
+pub fn evaluate_constants(data: &SimpleGarnishData, context: &mut BrowserContext, limit: usize) -> Result<(), String> {
+    let mut pending = context.take_pending_constants();
+
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(_, _, uses)| !uses.iter().any(|used| pending.iter().any(|(name, _, _)| name == used)));
+
+        match ready {
+            Some(index) => {
+                let (name, expression_index, _) = pending.remove(index);
+                evaluate_constant(&name, expression_index, data, context, limit)?;
+            }
+            None => {
+                let names: Vec<&str> = pending.iter().map(|(name, _, _)| name.as_str()).collect();
+                return Err(format!("Circular reference between {} {}", CONST_ANNOTATION, names.join(", ")));
+            }
+        }
+    }
+
     Ok(())
 }
+
+// execute a compiled expression once and store its value as a constant in the context
+fn evaluate_constant(
+    name: &str,
+    expression_index: usize,
+    data: &SimpleGarnishData,
+    context: &mut BrowserContext,
+    limit: usize,
+) -> Result<(), String> {
+    let (result_data, _, error) = run_expression(data, expression_index, context, &FormatOptions::new(), limit);
+
+    if let Some(e) = error {
+        return Err(format!("Error evaluating {} {}: {}", CONST_ANNOTATION, name, e));
+    }
+
+    let addr = result_data
+        .get_current_value()
+        .ok_or(format!("No value produced for {} {}", CONST_ANNOTATION, name))?;
+
+    context.add_symbol_value(name, addr, &result_data)
+}
+
+/// Names of the identifiers in tokens that are resolved when executed.
+pub fn identifiers_used(tokens: &[LexerToken]) -> Vec<String> {
+    (0..tokens.len())
+        .filter(|i| is_resolved_identifier(tokens, *i))
+        .map(|i| tokens[i].get_text().clone())
+        .collect()
+}
+
+/// Blocks of built in and registered annotations and the root tokens between them.
+pub fn collect_annotations(tokens: &Vec<LexerToken>, registry: &AnnotationRegistry) -> Result<Vec<TokenBlock>, String> {
+    collector(registry.sinks()).collect_tokens(tokens)
+}
+
+// built in sinks come first, so they take precedence over registered ones
+fn collector(registered: Vec<Sink>) -> Collector {
+    let mut sinks = vec![
+        named_expression_sink(DEF_ANNOTATION),
+        named_expression_sink(TEST_ANNOTATION),
+        named_expression_sink(CONST_ANNOTATION),
+        Sink::new(DOC_ANNOTATION).part(PartParser::new(PartBehavior::TokenCount(1))),
+    ];
+    sinks.extend(registered);
+
+    Collector::new(sinks)
+}
+
+// annotation followed by a name and an expression, `@Annotation name { expression }`
+fn named_expression_sink(annotation: &str) -> Sink {
+    Sink::new(annotation)
+        .part(PartParser::new(PartBehavior::TokenCount(1)))
+        .part(PartParser::new(PartBehavior::UntilToken(
+            TokenType::EndExpression,
+        )))
+}
+
+/// Text of each `@Doc` block that directly precedes a `@Def`, keyed by definition name.
+pub fn definition_documentation(blocks: &[TokenBlock]) -> HashMap<String, String> {
+    let mut documentation = HashMap::new();
+    let mut pending = None;
+
+    for block in blocks {
+        match block.annotation_text().as_str() {
+            DOC_ANNOTATION => pending = documentation_text(block),
+            DEF_ANNOTATION => {
+                if let (Some(text), Ok(name)) = (pending.take(), definition_name(block)) {
+                    documentation.insert(name.get_text().clone(), text);
+                }
+            }
+            // whitespace and comments between the two are allowed
+            "" if block.tokens().iter().all(|t| {
+                matches!(
+                    t.get_token_type(),
+                    TokenType::Whitespace | TokenType::Subexpression | TokenType::LineAnnotation
+                )
+            }) => {}
+            _ => pending = None,
+        }
+    }
+
+    documentation
+}
+
+// contents of the character list, parsed the same way as when compiled
+fn documentation_text(doc: &TokenBlock) -> Option<String> {
+    doc.parts()
+        .first()?
+        .iter()
+        .find(|t| t.get_token_type() == TokenType::CharList)
+        .and_then(|t| parse_char_list(t.get_text()).ok())
+}
+
+/// Identifier token naming a `@Def`, `@Test` or `@Const` block.
+pub fn definition_name(def: &TokenBlock) -> Result<&LexerToken, String> {
+    let name_part = def
+        .parts()
+        .first()
+        .ok_or(format!("No name part found for {} annotation", def.annotation_text()))?;
+
+    name_part
+        .iter()
+        .find(|t| t.get_token_type() == TokenType::Identifier)
+        .ok_or(format!("Expected identifier for {} name", def.annotation_text()))
+}
+
+/// Tokens between the braces of a `@Def`, `@Test` or `@Const` block.
+pub fn definition_expression(def: &TokenBlock) -> Result<&[LexerToken], String> {
+    let missing_expression = || {
+        format!(
+            "Expected expression after identifier for {} annotation",
+            def.annotation_text()
+        )
+    };
+
+    let expression_part = def
+        .parts()
+        .get(1)
+        .ok_or(format!("No expression found for {} annotation", def.annotation_text()))?;
+    let (start, _) = expression_part
+        .iter()
+        .enumerate()
+        .find(|(_, token)| token.get_token_type() == TokenType::StartExpression)
+        .ok_or_else(missing_expression)?;
+    let (end, _) = expression_part
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, token)| token.get_token_type() == TokenType::EndExpression)
+        .ok_or_else(missing_expression)?;
+
+    Ok(&expression_part[(start + 1)..end])
+}
+
+/// Parameter names of a `@Def` written as `@Def add(a, b) { a + b }`.
+/// Empty when the definition has no parameter list.
+pub fn definition_parameters(def: &TokenBlock) -> Result<Vec<&LexerToken>, String> {
+    let invalid = || format!("Invalid parameter list for {} annotation", def.annotation_text());
+
+    let declaration: Vec<&LexerToken> = def
+        .parts()
+        .get(1)
+        .map(|part| {
+            part.iter()
+                .take_while(|t| t.get_token_type() != TokenType::StartExpression)
+                .filter(|t| !matches!(t.get_token_type(), TokenType::Whitespace | TokenType::Subexpression))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let inner = match declaration.as_slice() {
+        [] => return Ok(vec![]),
+        [start, inner @ .., end]
+            if start.get_token_type() == TokenType::StartGroup && end.get_token_type() == TokenType::EndGroup =>
+        {
+            inner
+        }
+        _ => return Err(invalid()),
+    };
+
+    // identifiers separated by commas
+    let mut parameters: Vec<&LexerToken> = vec![];
+    for (i, token) in inner.iter().enumerate() {
+        let expected = match i % 2 {
+            0 => TokenType::Identifier,
+            _ => TokenType::Comma,
+        };
+        if token.get_token_type() != expected {
+            return Err(invalid());
+        }
+        if expected == TokenType::Identifier {
+            if parameters.iter().any(|p| p.get_text() == token.get_text()) {
+                return Err(format!("Duplicate parameter `{}`", token.get_text()));
+            }
+            parameters.push(token);
+        }
+    }
+
+    if inner.len() % 2 == 0 {
+        return Err(invalid());
+    }
+
+    Ok(parameters)
+}
+
+// replace parameter names with access on the input
+// a single parameter is the input itself, otherwise parameters are items of the input list
+fn bind_parameters(
+    def: &TokenBlock,
+    expression: &[LexerToken],
+    parameters: &[&LexerToken],
+) -> Result<Vec<LexerToken>, String> {
+    let mut bound = vec![];
+    let mut depth = 0;
+
+    for (i, token) in expression.iter().enumerate() {
+        match token.get_token_type() {
+            TokenType::StartExpression => depth += 1,
+            TokenType::EndExpression => depth -= 1,
+            TokenType::Identifier if is_resolved_identifier(expression, i) => {
+                if let Some(index) = parameters.iter().position(|p| p.get_text() == token.get_text()) {
+                    // nested expressions have their own input
+                    if depth > 0 {
+                        return Err(format!(
+                            "Parameter `{}` can't be used in a nested expression of {} annotation",
+                            token.get_text(),
+                            def.annotation_text()
+                        ));
+                    }
+
+                    bound.extend(parameter_access(index, parameters.len(), token));
+                    continue;
+                }
+            }
+            _ => (),
+        }
+
+        bound.push(token.clone());
+    }
+
+    Ok(bound)
+}
+
+fn parameter_access(index: usize, count: usize, at: &LexerToken) -> Vec<LexerToken> {
+    let token = |text: &str, token_type| LexerToken::new(text.to_string(), token_type, at.get_line(), at.get_column());
+
+    match count {
+        1 => vec![token("$", TokenType::Value)],
+        _ => vec![
+            token("(", TokenType::StartGroup),
+            token("$", TokenType::Value),
+            token(".", TokenType::Period),
+            token(&index.to_string(), TokenType::Number),
+            token(")", TokenType::EndGroup),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn constant_number() {
+        assert_eq!(result_of("@Const Answer { 40 + 2 }\n\nAnswer + 1"), Some("43".to_string()));
+    }
+
+    #[test]
+    fn constant_list() {
+        assert_eq!(
+            result_of("@Const Items { 1, \"two\", (3, :four) }\n\nItems"),
+            Some("1, \"two\", (3, :four)".to_string())
+        );
+        assert_eq!(
+            result_of("@Const Pairs { :first = 1, :second = 2 }\n\nPairs.second"),
+            Some("2".to_string())
+        );
+    }
+
+    #[test]
+    fn constant_using_definition() {
+        assert_eq!(
+            result_of("@Const Ten { add_5 ~ 5 }\n\n@Def add_5 { $ + 5 }\n\nTen * 2"),
+            Some("20".to_string())
+        );
+    }
+
+    #[test]
+    fn constant_using_include() {
+        let mut script = GarnishScript::new("main".to_string(), "@Const Ten { add_5 ~ 5 }\n\nTen * 2".to_string());
+        script.include("lib".to_string(), "@Def add_5 { $ + 5 }\n\nadd_5".to_string());
+
+        for _ in 0..2 {
+            script.compile();
+            script.execute();
+
+            assert_eq!(script.get_error(), None);
+            assert_eq!(script.get_execution_result(script.get_execution_count() as usize - 1), Some("20".to_string()));
+        }
+    }
+
+    #[test]
+    fn constant_expression() {
+        assert_eq!(
+            result_of("@Def add_5 { $ + 5 }\n\n@Const Adder { add_5 }\n\nAdder ~ 1"),
+            Some("6".to_string())
+        );
+    }
+
+    #[test]
+    fn constant_listed_in_context() {
+        let mut script = GarnishScript::new("main".to_string(), "@Const Answer { 42 }\n\n5".to_string());
+        script.compile();
+
+        let completions = script.completions("Ans".to_string(), 0, 3);
+        assert_eq!(completions[0].get_label(), "Answer");
+        assert_eq!(completions[0].get_detail(), "42");
+    }
+
+    #[test]
+    fn constant_evaluation_error() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def forever { forever ~ $ }\n\n@Const Never { forever ~ 1 }\n\n5".to_string(),
+        );
+        script.compile();
+
+        assert_eq!(
+            script.get_error(),
+            Some("Error compiling main: Error evaluating @Const Never: Instruction execution limit reached. Possibly an infinite loop.".to_string())
+        );
+    }
+
+    #[test]
+    fn constant_using_later_constant() {
+        assert_eq!(
+            result_of("@Const Area { Width * Height }\n\n@Const Width { 4 }\n\n@Const Height { Width + 1 }\n\nArea"),
+            Some("20".to_string())
+        );
+    }
+
+    #[test]
+    fn constants_referring_to_each_other() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Const Ready { 1 }\n\n@Const First { Second }\n\n@Const Second { First + Ready }\n\n5".to_string(),
+        );
+        script.compile();
+
+        assert_eq!(
+            script.get_error(),
+            Some("Error compiling main: Circular reference between @Const First, Second".to_string())
+        );
+    }
+
+    #[test]
+    fn definition_with_parameters() {
+        assert_eq!(
+            result_of("@Def add(a, b) { a + b }\n\nadd ~ (1, 2)"),
+            Some("3".to_string())
+        );
+        assert_eq!(
+            result_of("@Def point(x, y) { :x = x, :y = y * 2 }\n\n(point ~ (3, 4)).y"),
+            Some("8".to_string())
+        );
+    }
+
+    #[test]
+    fn definition_with_single_parameter() {
+        assert_eq!(result_of("@Def double(value) { value * 2 }\n\ndouble ~ 21"), Some("42".to_string()));
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let error_of = |source: &str| {
+            let mut script = GarnishScript::new("main".to_string(), source.to_string());
+            script.compile();
+            script.get_error()
+        };
+
+        assert_eq!(
+            error_of("@Def add(a, 5) { a }"),
+            Some("Error compiling main: Invalid parameter list for @Def annotation".to_string())
+        );
+        assert_eq!(
+            error_of("@Def add(a, a) { a }"),
+            Some("Error compiling main: Duplicate parameter `a`".to_string())
+        );
+        assert_eq!(
+            error_of("@Def add(a, b) { { a } }"),
+            Some("Error compiling main: Parameter `a` can't be used in a nested expression of @Def annotation".to_string())
+        );
+    }
+}
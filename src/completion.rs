@@ -0,0 +1,218 @@
+use crate::annotations::AnnotationRegistry;
+use crate::compile::{collect_annotations, definition_name, DEF_ANNOTATION};
+use crate::context::BrowserContext;
+use crate::display::{format_constant, FormatOptions};
+use crate::script::SourceDetails;
+use crate::tokens::identifier_at;
+use garnish_lang::compiler::lex::{Lexer, LexerToken};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompletionKind {
+    Constant,
+    Definition,
+    Include,
+    Function,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    label: String,
+    kind: CompletionKind,
+    detail: String,
+}
+
+#[wasm_bindgen]
+impl Completion {
+    pub fn get_label(&self) -> String {
+        self.label.clone()
+    }
+
+    pub fn get_kind(&self) -> CompletionKind {
+        self.kind
+    }
+
+    pub fn get_detail(&self) -> String {
+        self.detail.clone()
+    }
+}
+
+impl Completion {
+    fn new(label: &str, kind: CompletionKind, detail: String) -> Self {
+        Completion {
+            label: label.to_string(),
+            kind,
+            detail,
+        }
+    }
+}
+
+/// Candidates for the identifier being typed at the given zero based line and column.
+///
+/// `@Def`s in the given text are included even if it hasn't been compiled yet.
+pub fn completions(
+    text: &str,
+    line: usize,
+    column: usize,
+    context: &BrowserContext,
+    source: &SourceDetails,
+    includes: &[SourceDetails],
+    registry: &AnnotationRegistry,
+) -> Vec<Completion> {
+    let tokens: Vec<LexerToken> = Lexer::new(text).collect();
+    let prefix = identifier_prefix(&tokens, line, column);
+
+    let include_names: HashSet<&String> = includes.iter().map(|include| include.name()).collect();
+
+    let mut candidates = vec![];
+
+    if let Ok(blocks) = collect_annotations(&tokens, registry) {
+        for block in blocks.iter().filter(|b| b.annotation_text() == DEF_ANNOTATION) {
+            if let Ok(name) = definition_name(block) {
+                candidates.push(Completion::new(
+                    name.get_text(),
+                    CompletionKind::Definition,
+                    DEF_ANNOTATION.to_string(),
+                ));
+            }
+        }
+    }
+
+    for name in &include_names {
+        candidates.push(Completion::new(
+            name,
+            CompletionKind::Include,
+            "Include".to_string(),
+        ));
+    }
+
+    for name in context.expression_names() {
+        if name != source.name() && !include_names.contains(name) {
+            candidates.push(Completion::new(
+                name,
+                CompletionKind::Definition,
+                DEF_ANNOTATION.to_string(),
+            ));
+        }
+    }
+
+    for (name, value) in context.constants() {
+        candidates.push(Completion::new(
+            name,
+            CompletionKind::Constant,
+            format_constant(value, &FormatOptions::new()),
+        ));
+    }
+
+    for name in context.native_names() {
+        let detail = context.member(name).map(|member| member.get_signature()).unwrap_or_default();
+        candidates.push(Completion::new(name, CompletionKind::Function, detail));
+    }
+
+    let mut seen = HashSet::new();
+    let mut results: Vec<Completion> = candidates
+        .into_iter()
+        .filter(|c| c.label.starts_with(&prefix) && seen.insert(c.label.clone()))
+        .collect();
+
+    results.sort_by(|a, b| a.label.cmp(&b.label));
+    results
+}
+
+// text of identifier under the cursor, up to the cursor
+fn identifier_prefix(tokens: &[LexerToken], line: usize, column: usize) -> String {
+    identifier_at(tokens, line, column)
+        .map(|t| t.get_text().chars().take(column - t.get_column()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::CompletionKind;
+    use crate::script::GarnishScript;
+
+    #[test]
+    fn context_constants() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let completions = script.completions("Math::P".to_string(), 0, 7);
+
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].get_label(), "Math::PI");
+        assert_eq!(completions[0].get_kind(), CompletionKind::Constant);
+        assert_eq!(completions[0].get_detail(), std::f64::consts::PI.to_string());
+    }
+
+    #[test]
+    fn definitions_in_text() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let text = "@Def add_5 { $ + 5 }\n\nad".to_string();
+        let completions = script.completions(text, 2, 2);
+
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].get_label(), "add_5");
+        assert_eq!(completions[0].get_kind(), CompletionKind::Definition);
+    }
+
+    #[test]
+    fn compiled_definitions_and_includes() {
+        let mut script = GarnishScript::new("main".to_string(), "5".to_string());
+        script.include("helpers".to_string(), "@Def half { $ / 2 }\n\nhalf ~ $".to_string());
+        script.compile();
+
+        let completions = script.completions("h".to_string(), 0, 1);
+        let labels: Vec<(String, CompletionKind)> = completions
+            .iter()
+            .map(|c| (c.get_label(), c.get_kind()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            vec![
+                ("half".to_string(), CompletionKind::Definition),
+                ("helpers".to_string(), CompletionKind::Include),
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_definitions() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def old_name { 5 }\n\nold_name".to_string());
+        script.compile();
+        script.set_text("5".to_string());
+        script.compile();
+
+        assert!(script.completions("old".to_string(), 0, 3).is_empty());
+        assert_eq!(script.completions("Math::P".to_string(), 0, 7).len(), 1);
+    }
+
+    #[test]
+    fn no_prefix_lists_everything() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let completions = script.completions("5 + ".to_string(), 0, 4);
+        let members: usize = script.get_modules().iter().map(|module| module.get_members().len()).sum();
+
+        assert_eq!(completions.len(), members);
+    }
+
+    #[test]
+    fn native_functions() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let completions = script.completions("Str::sp".to_string(), 0, 7);
+
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].get_label(), "Str::split");
+        assert_eq!(completions[0].get_kind(), CompletionKind::Function);
+        assert_eq!(completions[0].get_detail(), "Str::split ~ (text, separator)");
+    }
+
+    #[test]
+    fn disabled_modules() {
+        let mut script = GarnishScript::new("main".to_string(), "".to_string());
+        script.set_module_enabled("Math".to_string(), false).unwrap();
+
+        assert!(script.completions("Math::".to_string(), 0, 6).is_empty());
+    }
+}
@@ -1,60 +1,113 @@
-use garnish_lang::simple::{symbol_value, DataError, SimpleData, SimpleGarnishData, SimpleNumber};
+use crate::catalog::{Capabilities, Member, MemberValue, Module};
+use crate::execution::DEFAULT_EXECUTION_LIMIT;
+use crate::library::random::Generator;
+use crate::library::time::Clock;
+use crate::library::{modules, NativeFunction, NativeResult};
+use garnish_lang::simple::{symbol_value, DataError, SimpleData, SimpleGarnishData};
 use garnish_lang::{GarnishContext, GarnishData, RuntimeError};
+use garnish_lang_utilities::data::copy_data_at_to_data;
 use garnish_lang_utilities::DataInfoProvider;
-use std::collections::HashMap;
+use regex::Regex;
+use std::collections::{HashMap, VecDeque};
+
+const MAX_CACHED_PATTERNS: usize = 64;
 
 pub struct BrowserContext {
     symbol_to_expression: HashMap<u64, usize>,
-    symbol_to_data: HashMap<u64, SimpleData>,
+    // addresses in constant_data
+    symbol_to_data: HashMap<u64, usize>,
+    constant_data: SimpleGarnishData,
     symbol_to_name: HashMap<u64, String>,
+    symbol_to_documentation: HashMap<u64, String>,
+    tests: Vec<(String, usize)>,
+    // `@Const` names, expression indices and identifiers they use, waiting to be evaluated
+    pending_constants: Vec<(String, usize, Vec<String>)>,
+    metadata: HashMap<String, Vec<String>>,
+    // indices in natives, resolved as external values
+    symbol_to_native: HashMap<u64, usize>,
+    natives: Vec<NativeFunction>,
+    modules: Vec<Module>,
+    // indices in modules, for members that only resolve while their module is enabled and allowed
+    symbol_to_module: HashMap<u64, usize>,
+    capabilities: Capabilities,
+    random: Generator,
+    clock: Clock,
+    // instructions left in the current execution, shared with expressions invoked by natives
+    remaining_instructions: usize,
+    // compiled `Regex::` patterns keyed by their text, with their text in the order they were added
+    patterns: HashMap<String, Regex>,
+    pattern_order: VecDeque<String>,
 }
 
-const MATH_PI_SYMBOL: &str = "Math::PI";
-const MATH_INTEGER_MAX_SYMBOL: &str = "Math::IntegerMax";
-const MATH_INTEGER_MIN_SYMBOL: &str = "Math::IntegerMin";
-const MATH_FLOAT_MAX_SYMBOL: &str = "Math::FloatMax";
-const MATH_FLOAT_MIN_SYMBOL: &str = "Math::FloatMin";
-
 impl BrowserContext {
     pub fn new() -> Self {
+        BrowserContext::with_capabilities(Capabilities::Full)
+    }
+
+    /// Context where only modules within the given capabilities resolve.
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
         let mut context = BrowserContext {
             symbol_to_expression: HashMap::new(),
             symbol_to_name: HashMap::new(),
             symbol_to_data: HashMap::new(),
+            constant_data: SimpleGarnishData::new(),
+            symbol_to_documentation: HashMap::new(),
+            tests: vec![],
+            pending_constants: vec![],
+            metadata: HashMap::new(),
+            symbol_to_native: HashMap::new(),
+            natives: vec![],
+            modules: vec![],
+            symbol_to_module: HashMap::new(),
+            capabilities,
+            random: Generator::new(0),
+            clock: Clock::System,
+            remaining_instructions: DEFAULT_EXECUTION_LIMIT,
+            patterns: HashMap::new(),
+            pattern_order: VecDeque::new(),
         };
 
-        context.add_symbol_data(
-            MATH_PI_SYMBOL,
-            SimpleData::Number(SimpleNumber::Float(std::f64::consts::PI)),
-        );
-        context.add_symbol_data(
-            MATH_INTEGER_MAX_SYMBOL,
-            SimpleData::Number(SimpleNumber::Integer(i32::MAX)),
-        );
-        context.add_symbol_data(
-            MATH_INTEGER_MIN_SYMBOL,
-            SimpleData::Number(SimpleNumber::Integer(i32::MIN)),
-        );
-        context.add_symbol_data(
-            MATH_FLOAT_MAX_SYMBOL,
-            SimpleData::Number(SimpleNumber::Float(f64::MAX)),
-        );
-        context.add_symbol_data(
-            MATH_FLOAT_MIN_SYMBOL,
-            SimpleData::Number(SimpleNumber::Float(f64::MIN)),
-        );
+        for module in modules() {
+            context.add_module(module);
+        }
 
         context
     }
 
+    /// Name of a symbol from compiling, or from the data for names made at runtime.
+    pub fn symbol_name<'a>(&'a self, symbol: u64, data: &'a SimpleGarnishData) -> Option<&'a String> {
+        self.symbol_to_name
+            .get(&symbol)
+            .or_else(|| data.get_data().get_symbol(symbol))
+    }
+
     pub fn add_symbol_name(&mut self, name: &str) {
         self.symbol_to_name.insert(symbol_value(name), name.to_string());
     }
 
+    /// Add a constant value that doesn't reference other data, such as a number or character list.
+    /// Use [`BrowserContext::add_symbol_value`] for lists, pairs and other compound values.
     pub fn add_symbol_data(&mut self, name: &str, data: SimpleData) {
         let symbol = symbol_value(name);
+        self.constant_data.get_data_mut().push(data);
         self.symbol_to_name.insert(symbol, name.to_string());
-        self.symbol_to_data.insert(symbol, data);
+        self.symbol_to_data
+            .insert(symbol, self.constant_data.get_data().len() - 1);
+    }
+
+    /// Add a constant by copying the value at the given address, along with any data it references.
+    pub fn add_symbol_value(
+        &mut self,
+        name: &str,
+        addr: usize,
+        data: &SimpleGarnishData,
+    ) -> Result<(), String> {
+        let symbol = symbol_value(name);
+        let addr = copy_value(addr, data, &mut self.constant_data)?;
+        self.symbol_to_name.insert(symbol, name.to_string());
+        self.symbol_to_data.insert(symbol, addr);
+
+        Ok(())
     }
 
     pub fn add_expression_mapping(
@@ -65,6 +118,271 @@ impl BrowserContext {
         let symbol = symbol_value(name);
         self.symbol_to_name.insert(symbol, name.to_string());
         self.symbol_to_expression.insert(symbol, expression_index);
+        // documentation is added again after mapping if still present
+        self.symbol_to_documentation.remove(&symbol);
+    }
+
+    /// Remove expressions, documentation and constants added while compiling, keeping built-in modules.
+    pub fn clear_definitions(&mut self) {
+        self.symbol_to_expression.clear();
+        self.symbol_to_documentation.clear();
+        self.symbol_to_data.clear();
+        self.constant_data = SimpleGarnishData::new();
+        self.pending_constants.clear();
+
+        let constants: Vec<(String, SimpleData)> = self
+            .modules
+            .iter()
+            .flat_map(|module| module.members())
+            .filter_map(|member| match member.value() {
+                MemberValue::Constant(value) => Some((member.name().clone(), value.clone())),
+                MemberValue::Function(_) => None,
+            })
+            .collect();
+
+        for (name, value) in constants {
+            self.add_symbol_data(&name, value);
+        }
+    }
+
+    /// Attach documentation to a mapped expression, from a `@Doc` annotation.
+    pub fn add_documentation(&mut self, name: &str, text: &str) {
+        self.symbol_to_documentation
+            .insert(symbol_value(name), text.to_string());
+    }
+
+    pub fn get_documentation(&self, name: &str) -> Option<&String> {
+        self.symbol_to_documentation.get(&symbol_value(name))
+    }
+
+    /// Register a `@Test` expression. Tests aren't resolvable by name.
+    pub fn add_test(&mut self, name: &str, expression_index: usize) {
+        self.tests.push((name.to_string(), expression_index));
+    }
+
+    /// Names and expression indices of tests, in the order they were compiled.
+    pub fn tests(&self) -> &[(String, usize)] {
+        &self.tests
+    }
+
+    pub fn clear_tests(&mut self) {
+        self.tests.clear();
+    }
+
+    /// Register a compiled `@Const` expression to evaluate once all sources are compiled.
+    pub fn add_pending_constant(&mut self, name: &str, expression_index: usize, uses: Vec<String>) {
+        self.pending_constants.push((name.to_string(), expression_index, uses));
+    }
+
+    pub fn take_pending_constants(&mut self) -> Vec<(String, usize, Vec<String>)> {
+        std::mem::take(&mut self.pending_constants)
+    }
+
+    /// Record a value produced by an annotation handler. Values for the same key are kept in order.
+    pub fn add_metadata(&mut self, key: &str, value: &str) {
+        self.metadata
+            .entry(key.to_string())
+            .or_default()
+            .push(value.to_string());
+    }
+
+    pub fn get_metadata(&self, key: &str) -> &[String] {
+        self.metadata.get(key).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn clear_metadata(&mut self) {
+        self.metadata.clear();
+    }
+
+    /// Names of all mapped expressions, sources and `@Def`s alike.
+    pub fn expression_names(&self) -> impl Iterator<Item = &String> {
+        self.symbol_to_expression
+            .keys()
+            .filter_map(move |symbol| self.symbol_to_name.get(symbol))
+    }
+
+    /// Add a function implemented in Rust, resolved by name and applied to its input.
+    pub fn add_native(&mut self, name: &str, function: NativeFunction) {
+        let symbol = symbol_value(name);
+        self.natives.push(function);
+        self.symbol_to_name.insert(symbol, name.to_string());
+        self.symbol_to_native.insert(symbol, self.natives.len() - 1);
+    }
+
+    /// Names of all native functions in enabled and allowed modules.
+    pub fn native_names(&self) -> impl Iterator<Item = &String> {
+        self.symbol_to_native
+            .keys()
+            .filter(move |symbol| self.is_available(**symbol))
+            .filter_map(move |symbol| self.symbol_to_name.get(symbol))
+    }
+
+    /// Register every member of a module under its full name.
+    pub fn add_module(&mut self, module: Module) {
+        for member in module.members() {
+            match member.value() {
+                MemberValue::Function(function) => self.add_native(member.name(), *function),
+                MemberValue::Constant(value) => self.add_symbol_data(member.name(), value.clone()),
+            }
+            self.symbol_to_module
+                .insert(symbol_value(member.name()), self.modules.len());
+        }
+
+        self.modules.push(module);
+    }
+
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    pub fn set_module_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        match self.modules.iter_mut().find(|module| module.name() == name) {
+            Some(module) => {
+                module.set_enabled(enabled);
+                Ok(())
+            }
+            None => Err(format!("No module named `{}`", name)),
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Catalog entry for a function or constant of an enabled and allowed module.
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        let symbol = symbol_value(name);
+        if !self.is_available(symbol) {
+            return None;
+        }
+
+        let module = self.modules.get(*self.symbol_to_module.get(&symbol)?)?;
+        module.members().iter().find(|member| member.name() == name)
+    }
+
+    /// Error for a member of an enabled module that needs more capabilities than the context has.
+    pub fn disallowed(&self, name: &str) -> Option<String> {
+        let module = self.modules.get(*self.symbol_to_module.get(&symbol_value(name))?)?;
+
+        match module.get_enabled() && module.get_requires() > self.capabilities {
+            true => Some(format!(
+                "`{}` isn't allowed with {:?} capabilities, the {} module requires {:?}",
+                name,
+                self.capabilities,
+                module.name(),
+                module.get_requires()
+            )),
+            false => None,
+        }
+    }
+
+    // symbols that aren't module members are always enabled
+    fn is_enabled(&self, symbol: u64) -> bool {
+        match self.symbol_to_module.get(&symbol) {
+            Some(index) => self.modules[*index].get_enabled(),
+            None => true,
+        }
+    }
+
+    fn is_available(&self, symbol: u64) -> bool {
+        match self.symbol_to_module.get(&symbol) {
+            Some(index) => self.is_enabled(symbol) && self.modules[*index].get_requires() <= self.capabilities,
+            None => true,
+        }
+    }
+
+    /// Call the native function for an external value, None if there isn't one.
+    pub fn call_native(
+        &mut self,
+        external: usize,
+        input_addr: usize,
+        data: &mut SimpleGarnishData,
+    ) -> Option<NativeResult> {
+        let function = *self.natives.get(external)?;
+        Some(function(self, input_addr, data))
+    }
+
+    /// Name of the native function for an external value.
+    pub fn native_name(&self, external: usize) -> Option<&String> {
+        self.symbol_to_native
+            .iter()
+            .find(|(_, index)| **index == external)
+            .and_then(|(symbol, _)| self.symbol_to_name.get(symbol))
+    }
+
+    /// Restart the generator used by `Random::` functions.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random = Generator::new(seed);
+    }
+
+    pub fn random(&mut self) -> &mut Generator {
+        &mut self.random
+    }
+
+    /// Clock used by `Time::now`.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Without full capabilities the system clock is replaced by one fixed at 0.
+    pub fn clock(&self) -> Clock {
+        match self.clock {
+            Clock::System if self.capabilities < Capabilities::Full => Clock::Fixed(0.0),
+            clock => clock,
+        }
+    }
+
+    /// Start counting instructions for an execution, including those of expressions invoked by natives.
+    pub fn set_instruction_budget(&mut self, limit: usize) {
+        self.remaining_instructions = limit;
+    }
+
+    /// Count an executed instruction, false once the budget is used up.
+    pub fn spend_instruction(&mut self) -> bool {
+        self.remaining_instructions = self.remaining_instructions.saturating_sub(1);
+        self.remaining_instructions > 0
+    }
+
+    pub fn remaining_instructions(&self) -> usize {
+        self.remaining_instructions
+    }
+
+    /// Compiled pattern for the given text, compiling it on first use.
+    /// Only the most recently added patterns are kept.
+    pub fn pattern(&mut self, pattern: &str) -> Result<&Regex, String> {
+        if !self.patterns.contains_key(pattern) {
+            let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+            if self.pattern_order.len() >= MAX_CACHED_PATTERNS {
+                if let Some(oldest) = self.pattern_order.pop_front() {
+                    self.patterns.remove(&oldest);
+                }
+            }
+            self.patterns.insert(pattern.to_string(), regex);
+            self.pattern_order.push_back(pattern.to_string());
+        }
+
+        Ok(&self.patterns[pattern])
+    }
+
+    /// Name mapped to the expression at the given jump table index, if any.
+    /// Every compiled source and `@Def` has its own expression, so there's at most one.
+    pub fn expression_name(&self, expression_index: usize) -> Option<&String> {
+        self.symbol_to_expression
+            .iter()
+            .find(|(_, index)| **index == expression_index)
+            .and_then(|(symbol, _)| self.symbol_to_name.get(symbol))
+    }
+
+    /// Names and values of all constants in enabled and allowed modules.
+    pub fn constants(&self) -> impl Iterator<Item = (&String, &SimpleData)> {
+        self.symbol_to_data
+            .iter()
+            .filter(move |(symbol, _)| self.is_available(**symbol))
+            .filter_map(move |(symbol, addr)| {
+                let name = self.symbol_to_name.get(symbol)?;
+                let data = self.constant_data.get_data().get(*addr)?;
+                Some((name, data))
+            })
     }
 }
 
@@ -80,27 +398,121 @@ impl GarnishContext<SimpleGarnishData> for BrowserContext {
                     .and_then(|addr| data.push_register(addr))?;
                 Ok(true)
             }
+            None if !self.is_enabled(symbol) => Ok(false),
+            None if !self.is_available(symbol) => {
+                let name = self.symbol_to_name.get(&symbol).cloned().unwrap_or_default();
+                let message = self.disallowed(&name).unwrap_or_default();
+                Err(RuntimeError::new_message(message))
+            }
             None => match self.symbol_to_data.get(&symbol) {
-                Some(v) => match v {
-                    SimpleData::Number(n) => {
-                        data.add_number(*n)
+                Some(addr) => {
+                    copy_value(*addr, &self.constant_data, data)
+                        .and_then(|addr| data.push_register(addr))?;
+                    Ok(true)
+                }
+                None => match self.symbol_to_native.get(&symbol) {
+                    Some(index) => {
+                        data.add_external(*index)
                             .and_then(|addr| data.push_register(addr))?;
                         Ok(true)
                     }
-                    _ => Ok(false),
+                    None => Ok(false),
                 },
-                None => Ok(false),
             },
         }
     }
+
+    fn apply(
+        &mut self,
+        external_value: usize,
+        input_addr: usize,
+        data: &mut SimpleGarnishData,
+    ) -> Result<bool, RuntimeError<DataError>> {
+        match self.call_native(external_value, input_addr, data) {
+            Some(result) => {
+                data.push_register(result?)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+// copy_data_at_to_data from utilities doesn't support expressions
+fn copy_value(
+    addr: usize,
+    from: &SimpleGarnishData,
+    to: &mut SimpleGarnishData,
+) -> Result<usize, DataError> {
+    let value = from
+        .get_data()
+        .get(addr)
+        .ok_or(DataError::from(format!("No data at addr {}", addr)))?;
+
+    match value {
+        SimpleData::Expression(index) => to.add_expression(*index),
+        SimpleData::Custom(_) => Err(DataError::from(
+            "Custom data can't be copied between data objects".to_string(),
+        )),
+        SimpleData::Pair(left, right) => {
+            let left = copy_value(*left, from, to)?;
+            let right = copy_value(*right, from, to)?;
+            to.add_pair((left, right))
+        }
+        SimpleData::Range(start, end) => {
+            let start = copy_value(*start, from, to)?;
+            let end = copy_value(*end, from, to)?;
+            to.add_range(start, end)
+        }
+        SimpleData::Concatenation(left, right) => {
+            let left = copy_value(*left, from, to)?;
+            let right = copy_value(*right, from, to)?;
+            to.add_concatenation(left, right)
+        }
+        SimpleData::Slice(value, range) => {
+            let value = copy_value(*value, from, to)?;
+            let range = copy_value(*range, from, to)?;
+            to.add_slice(value, range)
+        }
+        SimpleData::List(items, associations) => {
+            let copied = items
+                .iter()
+                .map(|item| Ok((copy_value(*item, from, to)?, associations.contains(item))))
+                .collect::<Result<Vec<(usize, bool)>, DataError>>()?;
+
+            to.start_list(copied.len())?;
+            for (item, is_association) in copied {
+                to.add_to_list(item, is_association)?;
+            }
+            to.end_list()
+        }
+        _ => copy_data_at_to_data(addr, from, to),
+    }
 }
 
 impl DataInfoProvider<SimpleGarnishData> for BrowserContext {
-    fn get_symbol_name(&self, sym: u64, _data: &SimpleGarnishData) -> Option<String> {
-        self.symbol_to_name.get(&sym).map(|name| format!(":{}", name))
+    fn get_symbol_name(&self, sym: u64, data: &SimpleGarnishData) -> Option<String> {
+        self.symbol_name(sym, data).map(|name| format!(":{}", name))
+    }
+
+    fn format_symbol_data(&self, sym: u64, data: &SimpleGarnishData) -> Option<String> {
+        self.symbol_name(sym, data).map(|name| format!(":{}", name))
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::{BrowserContext, MAX_CACHED_PATTERNS};
+
+    #[test]
+    fn patterns_bounded() {
+        let mut context = BrowserContext::new();
+        for i in 0..MAX_CACHED_PATTERNS + 10 {
+            context.pattern(&format!("a{{{}}}", i)).unwrap();
+        }
 
-    fn format_symbol_data(&self, sym: u64, _data: &SimpleGarnishData) -> Option<String> {
-        self.symbol_to_name.get(&sym).map(|name| format!(":{}", name))
+        assert_eq!(context.patterns.len(), MAX_CACHED_PATTERNS);
+        assert!(!context.patterns.contains_key("a{0}"));
+        assert!(context.patterns.contains_key(&format!("a{{{}}}", MAX_CACHED_PATTERNS + 9)));
     }
 }
@@ -0,0 +1,270 @@
+use crate::context::BrowserContext;
+use garnish_lang::simple::{SimpleData, SimpleGarnishData};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionRow {
+    index: usize,
+    instruction: String,
+    data: Option<usize>,
+    expression: Option<String>,
+}
+
+#[wasm_bindgen]
+impl InstructionRow {
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_instruction(&self) -> String {
+        self.instruction.clone()
+    }
+
+    /// Data address the instruction operates on, if it takes one.
+    pub fn get_data(&self) -> Option<usize> {
+        self.data
+    }
+
+    /// Name of the closest preceding named expression.
+    pub fn get_expression(&self) -> Option<String> {
+        self.expression.clone()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpTableRow {
+    index: usize,
+    point: usize,
+    name: Option<String>,
+}
+
+#[wasm_bindgen]
+impl JumpTableRow {
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    /// Instruction index the entry jumps to.
+    pub fn get_point(&self) -> usize {
+        self.point
+    }
+
+    /// Name mapped to the entry, unnamed entries are created for nested expressions and branches.
+    pub fn get_name(&self) -> Option<String> {
+        self.name.clone()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRow {
+    addr: usize,
+    kind: String,
+    value: String,
+}
+
+#[wasm_bindgen]
+impl DataRow {
+    pub fn get_addr(&self) -> usize {
+        self.addr
+    }
+
+    pub fn get_kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    /// Value as stored, compound values show the addresses they reference.
+    pub fn get_value(&self) -> String {
+        self.value.clone()
+    }
+}
+
+/// Instructions, jump table and data section of compiled data.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disassembly {
+    instructions: Vec<InstructionRow>,
+    jump_table: Vec<JumpTableRow>,
+    data: Vec<DataRow>,
+}
+
+#[wasm_bindgen]
+impl Disassembly {
+    pub fn get_instructions(&self) -> Vec<InstructionRow> {
+        self.instructions.clone()
+    }
+
+    pub fn get_jump_table(&self) -> Vec<JumpTableRow> {
+        self.jump_table.clone()
+    }
+
+    pub fn get_data(&self) -> Vec<DataRow> {
+        self.data.clone()
+    }
+
+    /// All three sections as text, with instructions grouped under the expression they belong to.
+    pub fn get_listing(&self) -> String {
+        let mut listing = String::from("instructions:\n");
+
+        let mut expression = None;
+        for row in &self.instructions {
+            if let Some(name) = self.jump_table.iter().find(|j| j.point == row.index).and_then(|j| j.name.as_ref()) {
+                if expression != Some(name) {
+                    listing.push_str(&format!("{}:\n", name));
+                    expression = Some(name);
+                }
+            }
+
+            match row.data {
+                Some(addr) => listing.push_str(&format!("  {:04} {} {}\n", row.index, row.instruction, addr)),
+                None => listing.push_str(&format!("  {:04} {}\n", row.index, row.instruction)),
+            }
+        }
+
+        listing.push_str("\njump table:\n");
+        for row in &self.jump_table {
+            match &row.name {
+                Some(name) => listing.push_str(&format!("  {:04} -> {:04} {}\n", row.index, row.point, name)),
+                None => listing.push_str(&format!("  {:04} -> {:04}\n", row.index, row.point)),
+            }
+        }
+
+        listing.push_str("\ndata:\n");
+        for row in &self.data {
+            listing.push_str(&format!("  {:04} {} {}\n", row.addr, row.kind, row.value));
+        }
+
+        listing
+    }
+}
+
+pub fn disassemble(data: &SimpleGarnishData, context: &BrowserContext) -> Disassembly {
+    let jump_table: Vec<JumpTableRow> = data
+        .get_jump_points()
+        .iter()
+        .enumerate()
+        .map(|(index, point)| JumpTableRow {
+            index,
+            point: *point,
+            name: context.expression_name(index).cloned(),
+        })
+        .collect();
+
+    let instructions = data
+        .get_instructions()
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| InstructionRow {
+            index,
+            instruction: format!("{:?}", instruction.get_instruction()),
+            data: instruction.get_data(),
+            expression: jump_table
+                .iter()
+                .filter(|row| row.name.is_some() && row.point <= index)
+                .max_by_key(|row| row.point)
+                .and_then(|row| row.name.clone()),
+        })
+        .collect();
+
+    let data = (0..data.get_data().len())
+        .filter_map(|addr| {
+            let value = data.get_data().get(addr)?;
+            Some(DataRow {
+                addr,
+                kind: format!("{:?}", value.get_data_type()),
+                value: data_value(value, data, context),
+            })
+        })
+        .collect();
+
+    Disassembly {
+        instructions,
+        jump_table,
+        data,
+    }
+}
+
+fn data_value(value: &SimpleData, data: &SimpleGarnishData, context: &BrowserContext) -> String {
+    match value {
+        SimpleData::Symbol(symbol) => data
+            .get_data()
+            .symbol_to_name()
+            .get(symbol)
+            .map(|name| format!(":{}", name))
+            .unwrap_or_else(|| symbol.to_string()),
+        SimpleData::Expression(index) => match context.expression_name(*index) {
+            Some(name) => format!("{} {}", index, name),
+            None => index.to_string(),
+        },
+        SimpleData::Number(number) => number.to_string(),
+        SimpleData::CharList(text) => format!("{:?}", text),
+        SimpleData::Pair(left, right) => format!("{} = {}", left, right),
+        SimpleData::Range(start, end) => format!("{}..{}", start, end),
+        SimpleData::Concatenation(left, right) => format!("{} <> {}", left, right),
+        SimpleData::Slice(value, range) => format!("{} ~ {}", value, range),
+        SimpleData::List(items, associations) => format!("items {:?} associations {:?}", items, associations),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    #[test]
+    fn jump_table_names() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def add_5 { $ + 5 }\n\nadd_5 ~ 10".to_string());
+        script.compile();
+
+        let disassembly = script.disassemble();
+        let names: Vec<Option<String>> = disassembly.get_jump_table().iter().map(|row| row.get_name()).collect();
+
+        assert_eq!(names, vec![Some("main".to_string()), Some("add_5".to_string())]);
+
+        let add_5_point = disassembly.get_jump_table()[1].get_point();
+        let instruction = &disassembly.get_instructions()[add_5_point];
+        assert_eq!(instruction.get_expression(), Some("add_5".to_string()));
+        assert_eq!(disassembly.get_instructions()[0].get_expression(), Some("main".to_string()));
+    }
+
+    #[test]
+    fn names_after_recompile() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def old_name { $ + 5 }\n\nold_name ~ 10".to_string());
+        script.compile();
+        script.set_text("@Def new_name { $ + 5 }\n\nnew_name ~ 10".to_string());
+        script.compile();
+
+        let disassembly = script.disassemble();
+        let names: Vec<Option<String>> = disassembly.get_jump_table().iter().map(|row| row.get_name()).collect();
+
+        assert_eq!(names, vec![Some("main".to_string()), Some("new_name".to_string())]);
+        assert!(!disassembly.get_listing().contains("old_name"));
+    }
+
+    #[test]
+    fn data_rows() {
+        let mut script = GarnishScript::new("main".to_string(), "\"text\", :sym = 5".to_string());
+        script.compile();
+
+        let data = script.disassemble().get_data();
+        let values: Vec<(String, String)> = data.iter().map(|row| (row.get_kind(), row.get_value())).collect();
+
+        assert!(values.contains(&("CharList".to_string(), "\"text\"".to_string())));
+        assert!(values.contains(&("Symbol".to_string(), ":sym".to_string())));
+        assert!(values.contains(&("Number".to_string(), "5".to_string())));
+    }
+
+    #[test]
+    fn listing() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def add_5 { $ + 5 }\n\nadd_5 ~ 10".to_string());
+        script.compile();
+
+        let listing = script.disassemble().get_listing();
+        assert!(listing.starts_with("instructions:\nmain:\n  0000 "));
+        assert!(listing.contains("\nadd_5:\n"));
+        assert!(listing.contains("\njump table:\n  0000 -> 0000 main\n"));
+        assert!(listing.contains("\ndata:\n  0000 Unit Unit\n"));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::context::BrowserContext;
+use garnish_lang::simple::{SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::GarnishData;
+use garnish_lang_utilities::{iterate_concatentation, DataInfoProvider};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// How character lists are quoted when formatted.
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Double,
+    Single,
+    None,
+}
+
+/// Options controlling how runtime values are turned into text.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    pretty: bool,
+    indent: usize,
+    max_depth: Option<usize>,
+    max_list_length: Option<usize>,
+    precision: Option<usize>,
+    quote_style: QuoteStyle,
+    symbol_names: bool,
+}
+
+#[wasm_bindgen]
+impl FormatOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        FormatOptions {
+            pretty: false,
+            indent: 2,
+            max_depth: None,
+            max_list_length: None,
+            precision: None,
+            quote_style: QuoteStyle::Double,
+            symbol_names: true,
+        }
+    }
+
+    pub fn get_pretty(&self) -> bool {
+        self.pretty
+    }
+
+    /// Place each list item on its own line, indented by nesting level.
+    pub fn set_pretty(&mut self, pretty: bool) {
+        self.pretty = pretty;
+    }
+
+    pub fn get_indent(&self) -> usize {
+        self.indent
+    }
+
+    pub fn set_indent(&mut self, indent: usize) {
+        self.indent = indent;
+    }
+
+    pub fn get_max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Nested values deeper than this are displayed as `...`.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn get_max_list_length(&self) -> Option<usize> {
+        self.max_list_length
+    }
+
+    /// Lists longer than this display their first items followed by `...`.
+    pub fn set_max_list_length(&mut self, max_list_length: Option<usize>) {
+        self.max_list_length = max_list_length;
+    }
+
+    pub fn get_precision(&self) -> Option<usize> {
+        self.precision
+    }
+
+    /// Number of decimal places used for float values.
+    pub fn set_precision(&mut self, precision: Option<usize>) {
+        self.precision = precision;
+    }
+
+    pub fn get_quote_style(&self) -> QuoteStyle {
+        self.quote_style
+    }
+
+    pub fn set_quote_style(&mut self, quote_style: QuoteStyle) {
+        self.quote_style = quote_style;
+    }
+
+    pub fn get_symbol_names(&self) -> bool {
+        self.symbol_names
+    }
+
+    /// Display symbols by name when known, or as their raw value when false.
+    pub fn set_symbol_names(&mut self, symbol_names: bool) {
+        self.symbol_names = symbol_names;
+    }
+}
+
+pub fn format_value(
+    addr: usize,
+    data: &SimpleGarnishData,
+    context: &BrowserContext,
+    options: &FormatOptions,
+) -> String {
+    format_value_at_depth(addr, data, context, options, 0)
+}
+
+/// Format a constant value stored outside of runtime data, such as those in [`BrowserContext`].
+pub fn format_constant(value: &SimpleData, options: &FormatOptions) -> String {
+    match value {
+        SimpleData::Unit => "()".to_string(),
+        SimpleData::True => "True".to_string(),
+        SimpleData::False => "False".to_string(),
+        SimpleData::Number(n) => format_number(*n, options),
+        SimpleData::Char(c) => format!("{:?}", c),
+        SimpleData::CharList(s) => quote(s, options),
+        v => format!("{:?}", v.get_data_type()),
+    }
+}
+
+fn format_value_at_depth(
+    addr: usize,
+    data: &SimpleGarnishData,
+    context: &BrowserContext,
+    options: &FormatOptions,
+    depth: usize,
+) -> String {
+    let value = match data.get_data().get(addr) {
+        None => return format!("[No data found at addr {:?}]", addr),
+        Some(value) => value,
+    };
+
+    let too_deep = options.max_depth.map(|max| depth > max).unwrap_or(false);
+
+    match value {
+        SimpleData::Unit => "()".to_string(),
+        SimpleData::True => "True".to_string(),
+        SimpleData::False => "False".to_string(),
+        SimpleData::Type(t) => format!("{:?}", t),
+        SimpleData::Number(n) => format_number(*n, options),
+        SimpleData::Char(c) => format!("{:?}", c),
+        SimpleData::Byte(b) => format!("{:?}", b),
+        SimpleData::Symbol(sym) => match options.symbol_names {
+            true => context
+                .get_symbol_name(*sym, data)
+                .unwrap_or(format!("[No name for Symbol {}]", sym)),
+            false => sym.to_string(),
+        },
+        SimpleData::Expression(index) => data
+            .get_jump_point(*index)
+            .map(|point| {
+                context
+                    .get_address_name(point, data)
+                    .unwrap_or(format!("Expression @ {}", point))
+            })
+            .unwrap_or(format!("[No jump point at index {}]", index)),
+        SimpleData::External(value) => context
+            .native_name(*value)
+            .cloned()
+            .unwrap_or(format!("{{external - {}}}", value)),
+        SimpleData::CharList(s) => quote(s, options),
+        SimpleData::ByteList(bytes) => bytes
+            .iter()
+            .map(|b| format!("'{}'", b))
+            .collect::<Vec<String>>()
+            .join(" "),
+        SimpleData::Range(start, end) => match (data.get_number(*start), data.get_number(*end)) {
+            (Ok(start), Ok(end)) => format!(
+                "{}..{}",
+                format_number(start, options),
+                format_number(end, options)
+            ),
+            _ => "[Could not get start and end of range]".to_string(),
+        },
+        SimpleData::Pair(..) if too_deep => "...".to_string(),
+        SimpleData::Pair(left, right) => format!(
+            "{} = {}",
+            format_value_at_depth(*left, data, context, options, depth + 1),
+            format_value_at_depth(*right, data, context, options, depth + 1)
+        ),
+        SimpleData::List(..) | SimpleData::Concatenation(..) | SimpleData::Slice(..)
+            if too_deep =>
+        {
+            "(...)".to_string()
+        }
+        SimpleData::List(items, _) => format_items(items, data, context, options, depth),
+        SimpleData::Concatenation(..) => {
+            let mut items = vec![];
+            match iterate_concatentation(addr, data, |item| items.push(item)) {
+                Ok(()) => format_items(&items, data, context, options, depth),
+                Err(_) => format!("[Failed to format Concatenation at {}]", addr),
+            }
+        }
+        SimpleData::Slice(value, range) => format_slice(*value, *range, data, context, options, depth),
+        SimpleData::Custom(_) => context
+            .format_custom_data(addr, data)
+            .unwrap_or(String::from("[No display for Custom data]")),
+    }
+}
+
+fn format_number(number: SimpleNumber, options: &FormatOptions) -> String {
+    match (number, options.precision) {
+        (SimpleNumber::Float(f), Some(precision)) => format!("{:.*}", precision, f),
+        (n, _) => n.to_string(),
+    }
+}
+
+fn quote(s: &str, options: &FormatOptions) -> String {
+    match options.quote_style {
+        QuoteStyle::Double => format!("{:?}", s),
+        QuoteStyle::Single => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+        QuoteStyle::None => s.to_string(),
+    }
+}
+
+fn format_slice(
+    value: usize,
+    range: usize,
+    data: &SimpleGarnishData,
+    context: &BrowserContext,
+    options: &FormatOptions,
+    depth: usize,
+) -> String {
+    let (start, end) = match data
+        .get_range(range)
+        .and_then(|(start, end)| Ok((data.get_number(start)?, data.get_number(end)?)))
+    {
+        // end is inclusive, widened so i32::MAX doesn't overflow
+        Ok((SimpleNumber::Integer(start), SimpleNumber::Integer(end))) => {
+            (start.max(0) as usize, (end as i64 + 1).max(0) as usize)
+        }
+        _ => return "[Could not get start and end of range of slice]".to_string(),
+    };
+
+    match data.get_data().get(value) {
+        Some(SimpleData::List(items, _)) => {
+            let end = end.min(items.len());
+            let items = items.get(start..end).unwrap_or(&[]);
+            format_items(items, data, context, options, depth)
+        }
+        Some(SimpleData::CharList(s)) => {
+            let s: String = s.chars().skip(start).take(end.saturating_sub(start)).collect();
+            quote(&s, options)
+        }
+        Some(SimpleData::ByteList(bytes)) => bytes
+            .iter()
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .map(|b| format!("'{}'", b))
+            .collect::<Vec<String>>()
+            .join(" "),
+        Some(other) => format!("[Invalid value for slice {:?}]", other.get_data_type()),
+        None => format!("[No data found at addr {:?}]", value),
+    }
+}
+
+fn format_items(
+    items: &[usize],
+    data: &SimpleGarnishData,
+    context: &BrowserContext,
+    options: &FormatOptions,
+    depth: usize,
+) -> String {
+    if items.is_empty() {
+        return String::from("(,)");
+    }
+
+    let shown = options.max_list_length.unwrap_or(items.len()).min(items.len());
+    let mut parts: Vec<String> = items[..shown]
+        .iter()
+        .map(|item| format_value_at_depth(*item, data, context, options, depth + 1))
+        .collect();
+
+    if shown < items.len() {
+        parts.push("...".to_string());
+    }
+
+    match (options.pretty, depth > 0) {
+        (false, true) => format!("({})", parts.join(", ")),
+        (false, false) => parts.join(", "),
+        (true, false) => parts.join(",\n"),
+        (true, true) => {
+            let inner = " ".repeat(options.indent * depth);
+            let outer = " ".repeat(options.indent * (depth - 1));
+            let body = parts
+                .iter()
+                .map(|part| format!("{}{}", inner, part))
+                .collect::<Vec<String>>()
+                .join(",\n");
+            format!("(\n{}\n{})", body, outer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::{FormatOptions, QuoteStyle};
+    use crate::script::GarnishScript;
+
+    fn result_with(source: &str, options: FormatOptions) -> String {
+        let mut script = GarnishScript::new("test".to_string(), source.to_string());
+        script.set_format_options(options);
+        script.compile();
+        script.execute();
+        script.get_execution_result(0).unwrap()
+    }
+
+    #[test]
+    fn slice_to_integer_max() {
+        assert_eq!(result_with("(1, 2, 3) ~ (1..Math::IntegerMax)", FormatOptions::new()), "2, 3");
+        assert_eq!(result_with("\"abc\" ~ (1..Math::IntegerMax)", FormatOptions::new()), "\"bc\"");
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(result_with("1, (2, 3), \"four\"", FormatOptions::new()), "1, (2, 3), \"four\"");
+    }
+
+    #[test]
+    fn pretty() {
+        let mut options = FormatOptions::new();
+        options.set_pretty(true);
+
+        assert_eq!(result_with("1, (2, 3)", options), "1,\n(\n  2,\n  3\n)");
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut options = FormatOptions::new();
+        options.set_max_depth(Some(1));
+
+        assert_eq!(result_with("1, (2, (3, 4))", options), "1, (2, (...))");
+    }
+
+    #[test]
+    fn max_list_length() {
+        let mut options = FormatOptions::new();
+        options.set_max_list_length(Some(2));
+
+        assert_eq!(result_with("1, 2, 3, 4", options), "1, 2, ...");
+    }
+
+    #[test]
+    fn precision() {
+        let mut options = FormatOptions::new();
+        options.set_precision(Some(2));
+
+        assert_eq!(result_with("Math::PI", options), "3.14");
+    }
+
+    #[test]
+    fn quote_style() {
+        let mut options = FormatOptions::new();
+        options.set_quote_style(QuoteStyle::Single);
+        assert_eq!(result_with("\"it's\"", options.clone()), "'it\\'s'");
+
+        options.set_quote_style(QuoteStyle::None);
+        assert_eq!(result_with("\"it's\"", options), "it's");
+    }
+
+    #[test]
+    fn raw_symbols() {
+        let mut options = FormatOptions::new();
+        options.set_symbol_names(false);
+
+        assert_eq!(
+            result_with(":my_symbol", options),
+            garnish_lang::simple::symbol_value("my_symbol").to_string()
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::context::BrowserContext;
+use crate::display::{format_value, FormatOptions};
+use crate::library::NativeResult;
+use garnish_lang::simple::{SimpleData, SimpleGarnishData, SimpleGarnishRuntime, SimpleRuntimeState};
+use garnish_lang::{GarnishData, GarnishRuntime, Instruction, RuntimeError};
+use web_sys::console;
+
+/// Number of instructions executed before assuming an infinite loop.
+pub const DEFAULT_EXECUTION_LIMIT: usize = 10000;
+
+const LIMIT_REACHED: &str = "Instruction execution limit reached. Possibly an infinite loop.";
+
+/// Execute from the current instruction until the end of execution, an error or the instruction limit.
+///
+/// Returns the final data, the number of instructions executed and any error.
+/// Instructions of expressions invoked by native functions count toward the same limit.
+/// Side effect values are logged to the console.
+pub fn run(
+    data: SimpleGarnishData,
+    context: &mut BrowserContext,
+    options: &FormatOptions,
+    limit: usize,
+) -> (SimpleGarnishData, usize, Option<String>) {
+    let mut runtime = SimpleGarnishRuntime::new(data);
+
+    let mut error = None;
+    context.set_instruction_budget(limit);
+
+    loop {
+        if let Some((Instruction::EndSideEffect, _)) = runtime.get_data().get_current_instruction() {
+            let formatted = runtime.get_data().get_registers().last()
+                .map(|addr| format_value(*addr, runtime.get_data(), context, options))
+                .unwrap_or("[Side Effect did not result in a value".to_string());
+
+            console::log_1(&formatted.into());
+        }
+
+        match runtime.execute_current_instruction(Some(context)) {
+            Err(e) => {
+                error = Some(e.get_message().clone());
+                break;
+            }
+            Ok(data) => match data.get_state() {
+                SimpleRuntimeState::Running => (),
+                SimpleRuntimeState::End => break,
+            },
+        }
+
+        if !context.spend_instruction() {
+            error = Some(LIMIT_REACHED.to_string());
+            break;
+        }
+    }
+
+    let count = limit - context.remaining_instructions();
+    (runtime.get_data_owned(), count, error)
+}
+
+/// Execute a single compiled expression, with unit as its input, in a copy of the given data.
+pub fn run_expression(
+    data: &SimpleGarnishData,
+    expression_index: usize,
+    context: &mut BrowserContext,
+    options: &FormatOptions,
+    limit: usize,
+) -> (SimpleGarnishData, usize, Option<String>) {
+    let mut data = data.clone();
+    let start = match data.get_jump_point(expression_index) {
+        Some(point) => point,
+        None => {
+            let error = format!("No jump point at index {}", expression_index);
+            return (data, 0, Some(error));
+        }
+    };
+
+    if let Err(e) = data
+        .push_value_stack(0)
+        .and_then(|_| data.set_instruction_cursor(start))
+    {
+        return (data, 0, Some(e.to_string()));
+    }
+
+    run(data, context, options, limit)
+}
+
+/// Apply a function value to an input during execution, from within a native function.
+///
+/// Expressions are run on the given data until they return, using the current execution's instruction limit.
+/// Native functions are called directly. Any other value results in unit.
+pub fn invoke(
+    function_addr: usize,
+    input_addr: usize,
+    data: &mut SimpleGarnishData,
+    context: &mut BrowserContext,
+) -> NativeResult {
+    match data.get_data().get(function_addr) {
+        Some(SimpleData::Expression(index)) => {
+            let index = *index;
+            invoke_expression(index, input_addr, data, context)
+        }
+        Some(SimpleData::External(external)) => {
+            let external = *external;
+            match context.call_native(external, input_addr, data) {
+                Some(result) => result,
+                None => Ok(data.add_unit()?),
+            }
+        }
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+// same as applying the expression, but returning to the current instruction once it ends
+fn invoke_expression(
+    expression_index: usize,
+    input_addr: usize,
+    data: &mut SimpleGarnishData,
+    context: &mut BrowserContext,
+) -> NativeResult {
+    let start = data.get_jump_point(expression_index).ok_or_else(|| {
+        RuntimeError::new_message(format!("No jump point at index {}", expression_index))
+    })?;
+
+    let depth = data.get_jump_path_vec().len();
+    data.push_jump_path(data.get_instruction_cursor())?;
+    data.push_value_stack(input_addr)?;
+    data.set_instruction_cursor(start)?;
+
+    let mut runtime = SimpleGarnishRuntime::new(std::mem::replace(data, SimpleGarnishData::new()));
+    let result = loop {
+        match runtime.execute_current_instruction(Some(&mut *context)) {
+            Err(e) => break Err(e),
+            Ok(info) if info.get_state() == SimpleRuntimeState::End => {
+                break Err(RuntimeError::new("Execution ended before expression returned"));
+            }
+            Ok(_) => (),
+        }
+
+        // end of the expression pops the jump path pushed above
+        if runtime.get_data().get_jump_path_vec().len() <= depth {
+            break Ok(());
+        }
+
+        if !context.spend_instruction() {
+            break Err(RuntimeError::new(LIMIT_REACHED));
+        }
+    };
+
+    *data = runtime.get_data_owned();
+    result?;
+
+    data.pop_register()
+        .ok_or_else(|| RuntimeError::new("No value produced by expression"))
+}
@@ -0,0 +1,359 @@
+use garnish_lang::compiler::lex::{lex, LexerToken, TokenType};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Options for [`format_source`].
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFormatOptions {
+    indent: usize,
+    max_line_length: usize,
+}
+
+#[wasm_bindgen]
+impl SourceFormatOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        SourceFormatOptions {
+            indent: 2,
+            max_line_length: 100,
+        }
+    }
+
+    pub fn get_indent(&self) -> usize {
+        self.indent
+    }
+
+    /// Number of spaces per nesting level.
+    pub fn set_indent(&mut self, indent: usize) {
+        self.indent = indent;
+    }
+
+    pub fn get_max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Lines longer than this are wrapped at the next space, where possible.
+    pub fn set_max_line_length(&mut self, max_line_length: usize) {
+        self.max_line_length = max_line_length;
+    }
+}
+
+/// Re-emit source text with consistent spacing, indentation and line wrapping.
+///
+/// Line breaks are kept where they are, with blank lines limited to one since they separate subexpressions.
+/// Comments and annotations are kept in place.
+#[wasm_bindgen]
+pub fn format_source(text: String, options: &SourceFormatOptions) -> Result<String, String> {
+    let tokens = lex(&text).map_err(|e| e.get_message().clone())?;
+
+    let mut formatter = Formatter::new(options);
+    for token in &tokens {
+        formatter.push(token);
+    }
+
+    let mut output = formatter.output.trim_end().to_string();
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+struct Formatter<'a> {
+    options: &'a SourceFormatOptions,
+    output: String,
+    line_length: usize,
+    depth: usize,
+    previous: Option<TokenType>,
+    had_space: bool,
+    pending_newlines: usize,
+    expecting_operand: bool,
+    previous_unary: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(options: &'a SourceFormatOptions) -> Self {
+        Formatter {
+            options,
+            output: String::new(),
+            line_length: 0,
+            depth: 0,
+            previous: None,
+            had_space: false,
+            pending_newlines: 0,
+            expecting_operand: true,
+            previous_unary: false,
+        }
+    }
+
+    fn push(&mut self, token: &LexerToken) {
+        let token_type = token.get_token_type();
+
+        match token_type {
+            TokenType::Whitespace | TokenType::Subexpression => {
+                self.had_space = true;
+                self.pending_newlines += token.get_text().matches('\n').count();
+                return;
+            }
+            TokenType::EndGroup | TokenType::EndExpression | TokenType::EndSideEffect => {
+                self.depth = self.depth.saturating_sub(1);
+            }
+            _ => (),
+        }
+
+        let text = match token_type {
+            TokenType::LineAnnotation => token.get_text().trim_end(),
+            _ => token.get_text().as_str(),
+        };
+
+        if self.previous.is_none() {
+            // leading whitespace is dropped
+            self.pending_newlines = 0;
+        }
+
+        if self.pending_newlines > 0 {
+            let newlines = self.pending_newlines.min(2);
+            self.output.truncate(self.output.trim_end_matches(' ').len());
+            self.output.push_str(&"\n".repeat(newlines));
+            self.push_indent(self.depth);
+        } else if let Some(previous) = self.previous {
+            let separator = self.separator(previous, token_type);
+            let fits = self.line_length + separator.len() + text.chars().count()
+                <= self.options.max_line_length;
+
+            // operators stay on the same line as their right operand
+            let can_wrap = token_type != TokenType::LineAnnotation && !is_binary(previous);
+
+            if separator == " " && !fits && can_wrap {
+                self.output.push('\n');
+                self.push_indent(self.depth + 1);
+            } else {
+                self.push_text(separator);
+            }
+        }
+
+        self.push_text(text);
+
+        // binary operator without a left operand, such as a leading negative sign
+        self.previous_unary = is_binary(token_type) && self.expecting_operand;
+        self.expecting_operand = is_binary(token_type)
+            || is_prefix(token_type)
+            || opens(token_type)
+            || token_type == TokenType::Comma;
+        self.previous = Some(token_type);
+        self.had_space = false;
+        self.pending_newlines = match token_type {
+            // comment text includes its newline
+            TokenType::LineAnnotation => token.get_text().matches('\n').count(),
+            _ => 0,
+        };
+
+        if opens(token_type) {
+            self.depth += 1;
+        }
+    }
+
+    fn separator(&self, previous: TokenType, current: TokenType) -> &'static str {
+        let closed_up = current == TokenType::Comma
+            || is_suffix(current)
+            || matches!(previous, TokenType::StartGroup | TokenType::StartSideEffect)
+            || matches!(current, TokenType::EndGroup | TokenType::EndSideEffect);
+
+        let spaced = matches!(
+            previous,
+            TokenType::StartExpression | TokenType::Comma | TokenType::Annotation
+        ) || current == TokenType::EndExpression;
+
+        let operator_spaced = is_binary(previous) || (is_binary(current) && !self.expecting_operand);
+
+        if closed_up {
+            ""
+        } else if spaced {
+            " "
+        } else if is_prefix(previous) || self.previous_unary || is_tight(previous) || is_tight(current) {
+            ""
+        } else if operator_spaced || self.had_space {
+            // adjacent operands keep a space if they had one, spaces between them are meaningful
+            " "
+        } else {
+            ""
+        }
+    }
+
+    fn push_indent(&mut self, depth: usize) {
+        let indent = " ".repeat(self.options.indent * depth);
+        self.output.push_str(&indent);
+        self.line_length = indent.len();
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.line_length += text.chars().count();
+    }
+}
+
+fn opens(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::StartGroup | TokenType::StartExpression | TokenType::StartSideEffect
+    )
+}
+
+fn is_prefix(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::AbsoluteValue
+            | TokenType::Opposite
+            | TokenType::BitwiseNot
+            | TokenType::Not
+            | TokenType::Tis
+            | TokenType::TypeOf
+            | TokenType::LeftInternal
+            | TokenType::PrefixIdentifier
+    )
+}
+
+fn is_suffix(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::EmptyApply
+            | TokenType::RightInternal
+            | TokenType::LengthInternal
+            | TokenType::SuffixIdentifier
+    )
+}
+
+// binary operators written without surrounding spaces
+fn is_tight(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Period
+            | TokenType::Range
+            | TokenType::StartExclusiveRange
+            | TokenType::EndExclusiveRange
+            | TokenType::ExclusiveRange
+    )
+}
+
+fn is_binary(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::PlusSign
+            | TokenType::Subtraction
+            | TokenType::Division
+            | TokenType::MultiplicationSign
+            | TokenType::ExponentialSign
+            | TokenType::IntegerDivision
+            | TokenType::Remainder
+            | TokenType::BitwiseAnd
+            | TokenType::BitwiseOr
+            | TokenType::BitwiseXor
+            | TokenType::BitwiseLeftShift
+            | TokenType::BitwiseRightShift
+            | TokenType::And
+            | TokenType::Or
+            | TokenType::Xor
+            | TokenType::Apply
+            | TokenType::ApplyTo
+            | TokenType::Reapply
+            | TokenType::JumpIfFalse
+            | TokenType::JumpIfTrue
+            | TokenType::ElseJump
+            | TokenType::TypeCast
+            | TokenType::TypeEqual
+            | TokenType::Equality
+            | TokenType::Inequality
+            | TokenType::LessThan
+            | TokenType::LessThanOrEqual
+            | TokenType::GreaterThan
+            | TokenType::GreaterThanOrEqual
+            | TokenType::Pair
+            | TokenType::Concatenation
+            | TokenType::InfixIdentifier
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatter::{format_source, SourceFormatOptions};
+    use crate::script::GarnishScript;
+
+    fn format(text: &str) -> String {
+        format_source(text.to_string(), &SourceFormatOptions::new()).unwrap()
+    }
+
+    #[test]
+    fn operator_spacing() {
+        assert_eq!(format("5+5  *   2"), "5 + 5 * 2");
+        assert_eq!(format("a  ~>b"), "a ~> b");
+        assert_eq!(format("x=10"), "x = 10");
+    }
+
+    #[test]
+    fn tight_and_unary_operators() {
+        assert_eq!(format("a . b"), "a.b");
+        assert_eq!(format("1 .. 5"), "1..5");
+        assert_eq!(format("-- 5 +  ++ $"), "--5 + ++$");
+        assert_eq!(format("-5 + 3"), "-5 + 3");
+        assert_eq!(format("f ~~"), "f~~");
+        assert_eq!(format("list .|"), "list.|");
+    }
+
+    #[test]
+    fn groups_and_lists() {
+        assert_eq!(format("( 1 ,2,   3 )"), "(1, 2, 3)");
+        assert_eq!(format("1   2    3"), "1 2 3");
+        assert_eq!(format("{$+5}"), "{ $ + 5 }");
+        assert_eq!(format("[ $ ]"), "[$]");
+    }
+
+    #[test]
+    fn indentation() {
+        assert_eq!(
+            format("@Def add_5 {\n$ + 5\n}\n\nadd_5 ~ (\n1,\n2\n)"),
+            "@Def add_5 {\n  $ + 5\n}\n\nadd_5 ~ (\n  1,\n  2\n)"
+        );
+    }
+
+    #[test]
+    fn blank_lines_limited() {
+        assert_eq!(format("\n\n5 + 5\n\n\n\n10"), "5 + 5\n\n10");
+    }
+
+    #[test]
+    fn comments_preserved() {
+        assert_eq!(format("@@ add things\n5+5 @@ trailing\n"), "@@ add things\n5 + 5 @@ trailing\n");
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut options = SourceFormatOptions::new();
+        options.set_max_line_length(10);
+
+        assert_eq!(
+            format_source("value_one + value_two + three".to_string(), &options).unwrap(),
+            "value_one\n  + value_two\n  + three"
+        );
+    }
+
+    #[test]
+    fn idempotent() {
+        let once = format("@Def add_5 {  $+5 }\n\n\n(1,2 , 3)  ~  add_5 @@ apply\n");
+        assert_eq!(format(&once), once);
+    }
+
+    #[test]
+    fn lex_error() {
+        assert!(format_source("5 + ?".to_string(), &SourceFormatOptions::new()).is_err());
+    }
+
+    #[test]
+    fn formatted_source_executes_the_same() {
+        let text = "@Def add_5 {$+5}\n\n\n5*10\n\nadd_5~$";
+        let mut script = GarnishScript::new("test".to_string(), format(text));
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_execution_result(0), Some("55".to_string()));
+    }
+}
@@ -0,0 +1,237 @@
+use garnish_lang::simple::SimpleGarnishData;
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// How much of each execution is kept in a script's history.
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    /// Keep full runtime data for every execution.
+    Full,
+    /// Keep only the formatted result and metadata of every execution.
+    ResultsOnly,
+    /// Keep full runtime data for the most recent execution, results only for the rest.
+    LastFull,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionInfo {
+    timestamp: f64,
+    instruction_count: usize,
+    input: Option<String>,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl ExecutionInfo {
+    /// Milliseconds since the Unix epoch when the execution finished.
+    pub fn get_timestamp(&self) -> f64 {
+        self.timestamp
+    }
+
+    pub fn get_instruction_count(&self) -> usize {
+        self.instruction_count
+    }
+
+    pub fn get_input(&self) -> Option<String> {
+        self.input.clone()
+    }
+
+    pub fn get_error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+impl ExecutionInfo {
+    pub fn new(
+        timestamp: f64,
+        instruction_count: usize,
+        input: Option<String>,
+        error: Option<String>,
+    ) -> Self {
+        ExecutionInfo {
+            timestamp,
+            instruction_count,
+            input,
+            error,
+        }
+    }
+}
+
+pub struct ExecutionRecord {
+    info: ExecutionInfo,
+    result: Option<String>,
+    data: Option<SimpleGarnishData>,
+}
+
+impl ExecutionRecord {
+    pub fn new(info: ExecutionInfo, result: Option<String>, data: SimpleGarnishData) -> Self {
+        ExecutionRecord {
+            info,
+            result,
+            data: Some(data),
+        }
+    }
+
+    pub fn info(&self) -> &ExecutionInfo {
+        &self.info
+    }
+
+    pub fn result(&self) -> Option<&String> {
+        self.result.as_ref()
+    }
+
+    pub fn data(&self) -> Option<&SimpleGarnishData> {
+        self.data.as_ref()
+    }
+}
+
+/// Executions of a script, oldest first, trimmed according to a limit and [`RetentionPolicy`].
+pub struct ExecutionHistory {
+    records: VecDeque<ExecutionRecord>,
+    limit: Option<usize>,
+    policy: RetentionPolicy,
+}
+
+impl ExecutionHistory {
+    pub fn new() -> Self {
+        ExecutionHistory {
+            records: VecDeque::new(),
+            limit: None,
+            policy: RetentionPolicy::Full,
+        }
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+        self.apply_retention();
+    }
+
+    pub fn policy(&self) -> RetentionPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: RetentionPolicy) {
+        self.policy = policy;
+        self.apply_retention();
+    }
+
+    pub fn push(&mut self, record: ExecutionRecord) {
+        self.records.push_back(record);
+        self.apply_retention();
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ExecutionRecord> {
+        self.records.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    fn apply_retention(&mut self) {
+        if let Some(limit) = self.limit {
+            while self.records.len() > limit {
+                self.records.pop_front();
+            }
+        }
+
+        let keep_data = match self.policy {
+            RetentionPolicy::Full => self.records.len(),
+            RetentionPolicy::ResultsOnly => 0,
+            RetentionPolicy::LastFull => 1,
+        };
+
+        let drop_count = self.records.len().saturating_sub(keep_data);
+        for record in self.records.iter_mut().take(drop_count) {
+            record.data = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::{ExecutionHistory, ExecutionInfo, ExecutionRecord, RetentionPolicy};
+    use garnish_lang::simple::SimpleGarnishData;
+
+    fn record(result: &str) -> ExecutionRecord {
+        ExecutionRecord::new(
+            ExecutionInfo::new(0.0, 1, None, None),
+            Some(result.to_string()),
+            SimpleGarnishData::new(),
+        )
+    }
+
+    fn results(history: &ExecutionHistory) -> Vec<String> {
+        (0..history.len())
+            .map(|i| history.get(i).unwrap().result().unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_everything_by_default() {
+        let mut history = ExecutionHistory::new();
+        history.push(record("1"));
+        history.push(record("2"));
+        history.push(record("3"));
+
+        assert_eq!(results(&history), vec!["1", "2", "3"]);
+        assert!((0..3).all(|i| history.get(i).unwrap().data().is_some()));
+    }
+
+    #[test]
+    fn limit_keeps_most_recent() {
+        let mut history = ExecutionHistory::new();
+        history.set_limit(Some(2));
+        history.push(record("1"));
+        history.push(record("2"));
+        history.push(record("3"));
+
+        assert_eq!(results(&history), vec!["2", "3"]);
+    }
+
+    #[test]
+    fn setting_limit_trims_existing() {
+        let mut history = ExecutionHistory::new();
+        history.push(record("1"));
+        history.push(record("2"));
+        history.push(record("3"));
+        history.set_limit(Some(1));
+
+        assert_eq!(results(&history), vec!["3"]);
+    }
+
+    #[test]
+    fn results_only_drops_data() {
+        let mut history = ExecutionHistory::new();
+        history.set_policy(RetentionPolicy::ResultsOnly);
+        history.push(record("1"));
+        history.push(record("2"));
+
+        assert_eq!(results(&history), vec!["1", "2"]);
+        assert!(history.get(0).unwrap().data().is_none());
+        assert!(history.get(1).unwrap().data().is_none());
+    }
+
+    #[test]
+    fn last_full_keeps_data_for_last() {
+        let mut history = ExecutionHistory::new();
+        history.set_policy(RetentionPolicy::LastFull);
+        history.push(record("1"));
+        history.push(record("2"));
+        history.push(record("3"));
+
+        assert!(history.get(0).unwrap().data().is_none());
+        assert!(history.get(1).unwrap().data().is_none());
+        assert!(history.get(2).unwrap().data().is_some());
+    }
+}
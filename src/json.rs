@@ -0,0 +1,93 @@
+use crate::context::BrowserContext;
+use crate::library::{add_bool, add_list, add_named_symbol, add_text, text_of};
+use garnish_lang::simple::{DataError, SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::GarnishData;
+use serde_json::{Map, Number, Value};
+
+// Mapping between JSON and Garnish values, shared by `Json::` functions and the host.
+//
+// null is unit, booleans are True and False, strings are character lists,
+// arrays are lists and objects are lists of associations with a symbol for each key.
+// Whole numbers that fit are integers, any others are floats.
+
+/// Add the Garnish value for a JSON value, with object keys named in the data.
+pub fn add_json(value: &Value, data: &mut SimpleGarnishData) -> Result<usize, DataError> {
+    match value {
+        Value::Null => data.add_unit(),
+        Value::Bool(b) => add_bool(*b, data),
+        Value::Number(n) => match n.as_i64().filter(|i| *i >= i32::MIN as i64 && *i <= i32::MAX as i64) {
+            Some(i) => data.add_number(SimpleNumber::Integer(i as i32)),
+            None => data.add_number(SimpleNumber::Float(n.as_f64().unwrap_or(f64::NAN))),
+        },
+        Value::String(s) => add_text(s, data),
+        Value::Array(values) => {
+            let items = values
+                .iter()
+                .map(|value| add_json(value, data))
+                .collect::<Result<Vec<usize>, _>>()?;
+            add_list(&items, data)
+        }
+        Value::Object(fields) => {
+            let mut items = vec![];
+            for (key, value) in fields {
+                let symbol = add_named_symbol(key, data)?;
+                let value = add_json(value, data)?;
+                items.push(data.add_pair((symbol, value))?);
+            }
+            add_list(&items, data)
+        }
+    }
+}
+
+/// JSON for the Garnish value at the given address.
+///
+/// A list of only associations is an object, a single association is an object with one key
+/// and a symbol on its own is its name. Values without a JSON form, like expressions and ranges, are None.
+pub fn json_of(addr: usize, data: &SimpleGarnishData, context: &BrowserContext) -> Option<Value> {
+    match data.get_data().get(addr)? {
+        SimpleData::Unit => Some(Value::Null),
+        SimpleData::True => Some(Value::Bool(true)),
+        SimpleData::False => Some(Value::Bool(false)),
+        SimpleData::Number(SimpleNumber::Integer(i)) => Some(Value::from(*i)),
+        SimpleData::Number(SimpleNumber::Float(f)) => Number::from_f64(*f).map(Value::Number),
+        SimpleData::Char(..) | SimpleData::CharList(..) | SimpleData::Concatenation(..) => text_of(addr, data).map(Value::String),
+        SimpleData::Symbol(symbol) => context.symbol_name(*symbol, data).cloned().map(Value::String),
+        SimpleData::Pair(..) => association(addr, data, context).map(|(key, value)| {
+            let mut fields = Map::new();
+            fields.insert(key, value);
+            Value::Object(fields)
+        }),
+        SimpleData::List(items, _) => {
+            let is_object = !items.is_empty()
+                && items.iter().all(|item| match data.get_data().get(*item) {
+                    Some(SimpleData::Pair(left, _)) => matches!(data.get_data().get(*left), Some(SimpleData::Symbol(_))),
+                    _ => false,
+                });
+
+            match is_object {
+                true => items
+                    .iter()
+                    .map(|item| association(*item, data, context))
+                    .collect::<Option<Map<String, Value>>>()
+                    .map(Value::Object),
+                false => items
+                    .iter()
+                    .map(|item| json_of(*item, data, context))
+                    .collect::<Option<Vec<Value>>>()
+                    .map(Value::Array),
+            }
+        }
+        _ => None,
+    }
+}
+
+// key and value of a pair with a symbol on the left
+fn association(addr: usize, data: &SimpleGarnishData, context: &BrowserContext) -> Option<(String, Value)> {
+    match data.get_data().get(addr)? {
+        SimpleData::Pair(left, right) => match data.get_data().get(*left)? {
+            SimpleData::Symbol(symbol) => Some((context.symbol_name(*symbol, data)?.clone(), json_of(*right, data, context)?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -2,3 +2,24 @@ mod utils;
 mod script;
 mod context;
 mod compile;
+mod annotations;
+mod ast;
+mod catalog;
+mod completion;
+mod disassembly;
+mod display;
+mod execution;
+mod formatter;
+mod history;
+mod json;
+mod library;
+mod lint;
+mod navigation;
+mod outline;
+mod reference;
+mod rename;
+mod testing;
+mod tokens;
+
+pub use annotations::{AnnotationHandler, AnnotationScope};
+pub use script::GarnishScript;
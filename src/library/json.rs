@@ -0,0 +1,97 @@
+use crate::catalog::Module;
+use crate::context::BrowserContext;
+use crate::json::{add_json, json_of};
+use crate::library::{add_text, text_of, NativeResult};
+use garnish_lang::simple::SimpleGarnishData;
+use garnish_lang::GarnishData;
+
+pub fn module() -> Module {
+    Module::new("Json", "Conversion between JSON text and Garnish values.")
+        .function(
+            "parse",
+            "Json::parse ~ text",
+            "Value of JSON text, objects are lists of associations and null is unit.",
+            "(Json::parse ~ \"\"\"{\"id\": 7}\"\"\").id",
+            parse,
+        )
+        .function(
+            "stringify",
+            "Json::stringify ~ value",
+            "JSON text of a value, lists of associations are objects.",
+            "Json::stringify ~ (:id = 7, :tags = (1, 2))",
+            stringify,
+        )
+}
+
+fn parse(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match text_of(input, data).and_then(|text| serde_json::from_str(&text).ok()) {
+        Some(value) => Ok(add_json(&value, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn stringify(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match json_of(input, data, context) {
+        Some(value) => Ok(add_text(&value.to_string(), data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_error(), None);
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            result_of(r#"Json::parse ~ """[1, 2.5, true, null, "text"]""""#),
+            Some(r#"1, 2.5, True, (), "text""#.to_string())
+        );
+        assert_eq!(
+            result_of(r#"Json::parse ~ """{"name": "garnish", "tags": [1, 2]}""""#),
+            Some(r#":name = "garnish", :tags = (1, 2)"#.to_string())
+        );
+        assert_eq!(result_of("Json::parse ~ \"3000000000\""), Some("3000000000".to_string()));
+        assert_eq!(result_of("Json::parse ~ \"{not json\""), Some("()".to_string()));
+    }
+
+    #[test]
+    fn field_access() {
+        assert_eq!(result_of(r#"(Json::parse ~ """{"user": {"id": 7}}""").user.id"#), Some("7".to_string()));
+    }
+
+    #[test]
+    fn stringify() {
+        assert_eq!(
+            result_of(r#"Json::stringify ~ (:name = "garnish", :count = 3, :valid = $?, :none = ())"#),
+            Some(r#""{\"name\":\"garnish\",\"count\":3,\"valid\":true,\"none\":null}""#.to_string())
+        );
+        assert_eq!(
+            result_of(r#"Json::stringify ~ (1, (2, 3), "a" <> "b")"#),
+            Some(r#""[1,[2,3],\"ab\"]""#.to_string())
+        );
+        assert_eq!(result_of("Json::stringify ~ (1, :a = 2)"), Some(r#""[1,{\"a\":2}]""#.to_string()));
+        assert_eq!(result_of("Json::stringify ~ { $ }"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = r#"{"a":[1,2.5,{"b":null}],"c":"text","d":false}"#;
+        assert_eq!(
+            result_of(&format!("Json::stringify ~ (Json::parse ~ \"\"\"{}\"\"\")", json)),
+            Some(format!("{:?}", json))
+        );
+        assert_eq!(
+            result_of(r#"Json::parse ~ (Json::stringify ~ (:x = (1, 2), :y = "z"))"#),
+            Some(r#":x = (1, 2), :y = "z""#.to_string())
+        );
+    }
+}
@@ -0,0 +1,541 @@
+use crate::catalog::Module;
+use crate::context::BrowserContext;
+use crate::execution::invoke;
+use crate::library::{add_list, arguments, integer_of, list_items, number_of, text_of, NativeResult};
+use garnish_lang::simple::{SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::{GarnishData, RuntimeError};
+use std::cmp::Ordering;
+
+const MAX_RANGE_LENGTH: i64 = 100_000;
+
+pub fn module() -> Module {
+    Module::new("List", "Functions on lists, applying Garnish expressions to their items.")
+        .function(
+            "map",
+            "List::map ~ (list, function)",
+            "List of the function's result for each item.",
+            "List::map ~ ((1, 2, 3), { $ * 2 })",
+            map,
+        )
+        .function(
+            "filter",
+            "List::filter ~ (list, function)",
+            "Items the function returns a value other than false or unit for.",
+            "List::filter ~ ((1, 2, 3, 4), { $ > 2 })",
+            filter,
+        )
+        .function(
+            "reduce",
+            "List::reduce ~ (list, function, initial)",
+            "Combine items in order, the function is given (accumulator, item).",
+            "List::reduce ~ ((1, 2, 3), { $.0 + $.1 }, 0)",
+            reduce,
+        )
+        .function(
+            "sort",
+            "List::sort ~ list",
+            "Items in ascending order, numbers by value and text alphabetically.",
+            "List::sort ~ (3, 1, 2)",
+            sort,
+        )
+        .function(
+            "sort_by",
+            "List::sort_by ~ (list, function)",
+            "Items in ascending order of the key the function returns for each.",
+            "List::sort_by ~ ((\"ccc\", \"a\", \"bb\"), Str::length)",
+            sort_by,
+        )
+        .function("reverse", "List::reverse ~ list", "Items in reverse order.", "List::reverse ~ (1, 2, 3)", reverse)
+        .function(
+            "zip",
+            "List::zip ~ (lists...)",
+            "Lists of the items at each position, as long as the shortest list.",
+            "List::zip ~ ((1, 2), (\"a\", \"b\"))",
+            zip,
+        )
+        .function(
+            "flatten",
+            "List::flatten ~ list",
+            "Items of nested lists in one list, one level deep.",
+            "List::flatten ~ ((1, 2), (3, 4))",
+            flatten,
+        )
+        .function(
+            "unique",
+            "List::unique ~ list",
+            "First of each equal item, in order.",
+            "List::unique ~ (1, 2, 1, 3)",
+            unique,
+        )
+        .function(
+            "group_by",
+            "List::group_by ~ (list, function)",
+            "Pairs of each key the function returns with the items that have it, in order of first appearance.",
+            "List::group_by ~ ((1, 2, 3, 4), { Math::mod ~ ($, 2) })",
+            group_by,
+        )
+        .function(
+            "range",
+            "List::range ~ end | (start, end) | (start, end, step)",
+            "Integers from start up to, not including, end. At most 100000 items.",
+            "List::range ~ (0, 10, 2)",
+            range,
+        )
+        .function("sum", "List::sum ~ list", "Sum of numbers.", "List::sum ~ (1, 2, 3)", sum)
+        .function("product", "List::product ~ list", "Product of numbers.", "List::product ~ (2, 3, 4)", product)
+}
+
+// (list, function) arguments
+fn list_and_function(input: usize, data: &SimpleGarnishData) -> Option<(Vec<usize>, usize)> {
+    match arguments(input, data).as_slice() {
+        [list, function] => Some((list_items(*list, data)?, *function)),
+        _ => None,
+    }
+}
+
+// same as conditions in the language, everything except false and unit
+fn is_truthy(addr: usize, data: &SimpleGarnishData) -> bool {
+    !matches!(data.get_data().get(addr), Some(SimpleData::False) | Some(SimpleData::Unit) | None)
+}
+
+// text that is equal for equal values, to compare values at different addresses
+fn value_key(addr: usize, data: &SimpleGarnishData) -> String {
+    match data.get_data().get(addr) {
+        Some(SimpleData::Number(n)) => format!("n{}", n),
+        Some(SimpleData::CharList(s)) => format!("s{:?}", s),
+        Some(SimpleData::Pair(left, right)) => format!("({}={})", value_key(*left, data), value_key(*right, data)),
+        Some(SimpleData::List(items, _)) => format!(
+            "[{}]",
+            items.iter().map(|item| value_key(*item, data)).collect::<Vec<String>>().join(",")
+        ),
+        Some(value) => format!("{:?}", value),
+        None => String::new(),
+    }
+}
+
+// numbers compare by value and text alphabetically, other values can't be sorted
+fn compare(left: usize, right: usize, data: &SimpleGarnishData) -> Option<Ordering> {
+    match (number_of(left, data), number_of(right, data)) {
+        (Some(left), Some(right)) => as_float(left).partial_cmp(&as_float(right)),
+        _ => Some(text_of(left, data)?.cmp(&text_of(right, data)?)),
+    }
+}
+
+fn as_float(number: SimpleNumber) -> f64 {
+    match number {
+        SimpleNumber::Integer(i) => i as f64,
+        SimpleNumber::Float(f) => f,
+    }
+}
+
+// items ordered by their keys, None if any two keys can't be compared
+fn sorted(items: Vec<usize>, keys: &[usize], data: &SimpleGarnishData) -> Option<Vec<usize>> {
+    let mut indices: Vec<usize> = (0..items.len()).collect();
+    let mut comparable = true;
+    indices.sort_by(|a, b| {
+        compare(keys[*a], keys[*b], data).unwrap_or_else(|| {
+            comparable = false;
+            Ordering::Equal
+        })
+    });
+
+    match comparable {
+        true => Some(indices.into_iter().map(|i| items[i]).collect()),
+        false => None,
+    }
+}
+
+fn map(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let (items, function) = match list_and_function(input, data) {
+        Some(arguments) => arguments,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let mut results = vec![];
+    for item in items {
+        results.push(invoke(function, item, data, context)?);
+    }
+
+    Ok(add_list(&results, data)?)
+}
+
+fn filter(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let (items, function) = match list_and_function(input, data) {
+        Some(arguments) => arguments,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let mut kept = vec![];
+    for item in items {
+        let result = invoke(function, item, data, context)?;
+        if is_truthy(result, data) {
+            kept.push(item);
+        }
+    }
+
+    Ok(add_list(&kept, data)?)
+}
+
+// (list, function, initial), the function is given (accumulator, item)
+fn reduce(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let (items, function, initial) = match arguments(input, data).as_slice() {
+        [list, function, initial] => match list_items(*list, data) {
+            Some(items) => (items, *function, *initial),
+            None => return Ok(data.add_unit()?),
+        },
+        _ => return Ok(data.add_unit()?),
+    };
+
+    let mut accumulator = initial;
+    for item in items {
+        let pair = add_list(&[accumulator, item], data)?;
+        accumulator = invoke(function, pair, data, context)?;
+    }
+
+    Ok(accumulator)
+}
+
+fn sort(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = list_items(input, data).and_then(|items| {
+        let keys = items.clone();
+        sorted(items, &keys, data)
+    });
+
+    match result {
+        Some(items) => Ok(add_list(&items, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn sort_by(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let (items, function) = match list_and_function(input, data) {
+        Some(arguments) => arguments,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let mut keys = vec![];
+    for item in &items {
+        keys.push(invoke(function, *item, data, context)?);
+    }
+
+    match sorted(items, &keys, data) {
+        Some(items) => Ok(add_list(&items, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn reverse(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match list_items(input, data) {
+        Some(items) => {
+            let reversed: Vec<usize> = items.into_iter().rev().collect();
+            Ok(add_list(&reversed, data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+// lists of items at the same position, as long as the shortest list
+fn zip(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let lists = match arguments(input, data)
+        .iter()
+        .map(|list| list_items(*list, data))
+        .collect::<Option<Vec<Vec<usize>>>>()
+    {
+        Some(lists) if lists.len() > 1 => lists,
+        _ => return Ok(data.add_unit()?),
+    };
+
+    let length = lists.iter().map(Vec::len).min().unwrap_or(0);
+    let mut zipped = vec![];
+    for i in 0..length {
+        let items: Vec<usize> = lists.iter().map(|list| list[i]).collect();
+        zipped.push(add_list(&items, data)?);
+    }
+
+    Ok(add_list(&zipped, data)?)
+}
+
+// one level, items that aren't lists are kept as is
+fn flatten(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match list_items(input, data) {
+        Some(items) => {
+            let flattened: Vec<usize> = items
+                .into_iter()
+                .flat_map(|item| list_items(item, data).unwrap_or_else(|| vec![item]))
+                .collect();
+            Ok(add_list(&flattened, data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+// first of each equal value, in order
+fn unique(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match list_items(input, data) {
+        Some(items) => {
+            let mut seen = std::collections::HashSet::new();
+            let unique: Vec<usize> = items
+                .into_iter()
+                .filter(|item| seen.insert(value_key(*item, data)))
+                .collect();
+            Ok(add_list(&unique, data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+// pairs of key = items with that key, in order of first appearance
+// symbol keys can be accessed by name, `(List::group_by ~ (items, kind)).fruit`
+fn group_by(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let (items, function) = match list_and_function(input, data) {
+        Some(arguments) => arguments,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let mut groups: Vec<(String, usize, Vec<usize>)> = vec![];
+    for item in items {
+        let key = invoke(function, item, data, context)?;
+        let key_text = value_key(key, data);
+        match groups.iter_mut().find(|(text, _, _)| *text == key_text) {
+            Some((_, _, group)) => group.push(item),
+            None => groups.push((key_text, key, vec![item])),
+        }
+    }
+
+    let mut pairs = vec![];
+    for (_, key, group) in groups {
+        let list = add_list(&group, data)?;
+        pairs.push(data.add_pair((key, list))?);
+    }
+
+    Ok(add_list(&pairs, data)?)
+}
+
+// integers from start up to, not including, end
+// `List::range ~ 3` is 0, 1, 2 and `List::range ~ (0, 10, 5)` is 0, 5
+fn range(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let bounds = arguments(input, data)
+        .iter()
+        .map(|addr| integer_of(*addr, data))
+        .collect::<Option<Vec<i32>>>();
+
+    let (start, end, step) = match bounds.as_deref() {
+        Some([end]) => (0, *end, 1),
+        Some([start, end]) => (*start, *end, 1),
+        Some([start, end, step]) if *step != 0 => (*start, *end, *step),
+        _ => return Ok(data.add_unit()?),
+    };
+
+    let length = (end as i64 - start as i64 + step as i64 - step.signum() as i64) / step as i64;
+    if length > MAX_RANGE_LENGTH {
+        return Err(RuntimeError::new_message(format!(
+            "List::range of {} items is over the limit of {}",
+            length, MAX_RANGE_LENGTH
+        )));
+    }
+
+    let mut items = vec![];
+    let mut current = start as i64;
+    while (step > 0 && current < end as i64) || (step < 0 && current > end as i64) {
+        items.push(data.add_number(SimpleNumber::Integer(current as i32))?);
+        current += step as i64;
+    }
+
+    Ok(add_list(&items, data)?)
+}
+
+// integers while all items are integers and the result fits, floats otherwise
+fn fold_numbers(
+    input: usize,
+    data: &mut SimpleGarnishData,
+    initial: i32,
+    integer: fn(i32, i32) -> Option<i32>,
+    float: fn(f64, f64) -> f64,
+) -> NativeResult {
+    let numbers = list_items(input, data)
+        .and_then(|items| items.iter().map(|item| number_of(*item, data)).collect::<Option<Vec<SimpleNumber>>>());
+
+    let numbers = match numbers {
+        Some(numbers) => numbers,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let result = numbers
+        .iter()
+        .try_fold(initial, |total, number| match number {
+            SimpleNumber::Integer(i) => integer(total, *i),
+            SimpleNumber::Float(_) => None,
+        })
+        .map(SimpleNumber::Integer)
+        .unwrap_or_else(|| {
+            SimpleNumber::Float(numbers.iter().fold(initial as f64, |total, n| float(total, as_float(*n))))
+        });
+
+    Ok(data.add_number(result)?)
+}
+
+fn sum(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    fold_numbers(input, data, 0, i32::checked_add, |a, b| a + b)
+}
+
+fn product(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    fold_numbers(input, data, 1, i32::checked_mul, |a, b| a * b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_error(), None);
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn map() {
+        assert_eq!(
+            result_of("@Def double { $ * 2 }\n\nList::map ~ ((1, 2, 3), double)"),
+            Some("2, 4, 6".to_string())
+        );
+        assert_eq!(
+            result_of("List::map ~ ((\"a\", \"b\"), Str::upper)"),
+            Some("\"A\", \"B\"".to_string())
+        );
+        assert_eq!(result_of("List::map ~ (5, Str::upper)"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn filter() {
+        assert_eq!(
+            result_of("@Def big { $ > 2 }\n\nList::filter ~ ((1, 2, 3, 4), big)"),
+            Some("3, 4".to_string())
+        );
+    }
+
+    #[test]
+    fn reduce() {
+        assert_eq!(
+            result_of("@Def add(total, item) { total + item }\n\nList::reduce ~ ((1, 2, 3, 4), add, 10)"),
+            Some("20".to_string())
+        );
+    }
+
+    #[test]
+    fn nested_functions() {
+        assert_eq!(
+            result_of("@Def double { $ * 2 }\n\n@Def double_all { List::map ~ ($, double) }\n\nList::map ~ (((1, 2), (3, 4)), double_all)"),
+            Some("(2, 4), (6, 8)".to_string())
+        );
+    }
+
+    #[test]
+    fn sort() {
+        assert_eq!(result_of("List::sort ~ (3, 1.5, 2)"), Some("1.5, 2, 3".to_string()));
+        assert_eq!(result_of("List::sort ~ (\"b\", \"c\", \"a\")"), Some("\"a\", \"b\", \"c\"".to_string()));
+        assert_eq!(result_of("List::sort ~ (\"b\", 1)"), Some("()".to_string()));
+        assert_eq!(
+            result_of("List::sort_by ~ ((\"ccc\", \"a\", \"bb\"), Str::length)"),
+            Some("\"a\", \"bb\", \"ccc\"".to_string())
+        );
+    }
+
+    #[test]
+    fn reverse() {
+        assert_eq!(result_of("List::reverse ~ (1, 2, 3)"), Some("3, 2, 1".to_string()));
+    }
+
+    #[test]
+    fn zip() {
+        assert_eq!(
+            result_of("List::zip ~ ((1, 2, 3), (\"a\", \"b\"))"),
+            Some("(1, \"a\"), (2, \"b\")".to_string())
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        assert_eq!(result_of("List::flatten ~ ((1, 2), 3, (4, (5, 6)))"), Some("1, 2, 3, 4, (5, 6)".to_string()));
+    }
+
+    #[test]
+    fn unique() {
+        assert_eq!(
+            result_of("List::unique ~ (1, 2, 1, \"a\", \"a\", (1, 2), (1, 2))"),
+            Some("1, 2, \"a\", (1, 2)".to_string())
+        );
+    }
+
+    #[test]
+    fn group_by() {
+        assert_eq!(
+            result_of("@Def parity { (Math::mod ~ ($, 2)) == 0 }\n\nList::group_by ~ ((1, 2, 3, 4, 5), parity)"),
+            Some("False = (1, 3, 5), True = (2, 4)".to_string())
+        );
+        assert_eq!(
+            result_of("@Def kind { $.kind }\n\n(List::group_by ~ (((:kind = :fruit, :name = \"apple\"), (:kind = :nut, :name = \"pecan\")), kind)).nut"),
+            Some("(:kind = :nut, :name = \"pecan\")".to_string())
+        );
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(result_of("List::range ~ 3"), Some("0, 1, 2".to_string()));
+        assert_eq!(result_of("List::range ~ (2, 5)"), Some("2, 3, 4".to_string()));
+        assert_eq!(result_of("List::range ~ (0, 10, 5)"), Some("0, 5".to_string()));
+        assert_eq!(result_of("List::range ~ (3, 0, 0 - 1)"), Some("3, 2, 1".to_string()));
+        assert_eq!(result_of("List::range ~ (0, 3, 0)"), Some("()".to_string()));
+        assert_eq!(result_of("List::range ~ (0, 7, 3)"), Some("0, 3, 6".to_string()));
+        assert_eq!(result_of("List::range ~ (5, 0)"), Some("(,)".to_string()));
+    }
+
+    #[test]
+    fn range_over_limit() {
+        let mut script = GarnishScript::new("main".to_string(), "List::range ~ (0, 100001)".to_string());
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_error(), Some("List::range of 100001 items is over the limit of 100000".to_string()));
+    }
+
+    #[test]
+    fn sum_and_product() {
+        assert_eq!(result_of("List::sum ~ (1, 2, 3)"), Some("6".to_string()));
+        assert_eq!(result_of("List::sum ~ (1, 2.5)"), Some("3.5".to_string()));
+        assert_eq!(result_of("List::product ~ (2, 3, 4)"), Some("24".to_string()));
+        assert_eq!(result_of("List::sum ~ (1, \"2\")"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn error_in_function() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def forever { forever ~ $ }\n\nList::map ~ ((1, 2), forever)".to_string(),
+        );
+        script.compile();
+        script.execute();
+
+        assert_eq!(
+            script.get_error(),
+            Some("Instruction execution limit reached. Possibly an infinite loop.".to_string())
+        );
+    }
+
+    #[test]
+    fn nested_instructions_share_limit() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def slow { List::sum ~ (List::map ~ (List::range ~ 20, { $ * 2 })) }\n\nList::map ~ (List::range ~ 500, slow)"
+                .to_string(),
+        );
+        script.compile();
+        script.execute();
+
+        assert_eq!(
+            script.get_error(),
+            Some("Instruction execution limit reached. Possibly an infinite loop.".to_string())
+        );
+    }
+}
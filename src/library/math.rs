@@ -0,0 +1,394 @@
+use crate::catalog::Module;
+use crate::context::BrowserContext;
+use crate::library::{add_list, arguments, number_of, NativeResult};
+use garnish_lang::simple::{SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::GarnishData;
+
+pub fn module() -> Module {
+    let float = |f| SimpleData::Number(SimpleNumber::Float(f));
+    let integer = |i| SimpleData::Number(SimpleNumber::Integer(i));
+
+    Module::new("Math", "Numeric constants and functions on integers and floats.")
+        .constant("PI", "Ratio of a circle's circumference to its diameter.", float(std::f64::consts::PI))
+        .constant("E", "Base of the natural logarithm.", float(std::f64::consts::E))
+        .constant("Tau", "Ratio of a circle's circumference to its radius.", float(2.0 * std::f64::consts::PI))
+        .constant("Infinity", "Float larger than any other number.", float(f64::INFINITY))
+        .constant("NaN", "Float that isn't a number, not equal to anything including itself.", float(f64::NAN))
+        .constant("IntegerMax", "Largest integer.", integer(i32::MAX))
+        .constant("IntegerMin", "Smallest integer.", integer(i32::MIN))
+        .constant("FloatMax", "Largest finite float.", float(f64::MAX))
+        .constant("FloatMin", "Smallest finite float.", float(f64::MIN))
+        .function("abs", "Math::abs ~ number", "Absolute value, keeping the number's type.", "Math::abs ~ (0 - 5)", abs)
+        .function("floor", "Math::floor ~ number", "Largest integer not greater than the number.", "Math::floor ~ 2.7", floor)
+        .function("ceil", "Math::ceil ~ number", "Smallest integer not less than the number.", "Math::ceil ~ 2.1", ceil)
+        .function("round", "Math::round ~ number", "Nearest integer, halves round away from zero.", "Math::round ~ 2.5", round)
+        .function("sqrt", "Math::sqrt ~ number", "Square root as a float.", "Math::sqrt ~ 16", sqrt)
+        .function(
+            "pow",
+            "Math::pow ~ (base, exponent)",
+            "Base raised to the exponent, integer powers stay integers unless they overflow.",
+            "Math::pow ~ (2, 10)",
+            pow,
+        )
+        .function(
+            "log",
+            "Math::log ~ number | (number, base)",
+            "Natural logarithm, or logarithm in the given base.",
+            "Math::log ~ (8, 2)",
+            log,
+        )
+        .function("exp", "Math::exp ~ number", "E raised to the number.", "Math::exp ~ 1", exp)
+        .function("sin", "Math::sin ~ radians", "Sine of an angle in radians.", "Math::sin ~ Math::PI", sin)
+        .function("cos", "Math::cos ~ radians", "Cosine of an angle in radians.", "Math::cos ~ Math::PI", cos)
+        .function("tan", "Math::tan ~ radians", "Tangent of an angle in radians.", "Math::tan ~ 1", tan)
+        .function("asin", "Math::asin ~ number", "Arcsine in radians.", "Math::asin ~ 1", asin)
+        .function("acos", "Math::acos ~ number", "Arccosine in radians.", "Math::acos ~ 1", acos)
+        .function("atan", "Math::atan ~ number", "Arctangent in radians.", "Math::atan ~ 1", atan)
+        .function(
+            "atan2",
+            "Math::atan2 ~ (y, x)",
+            "Angle in radians of the point (x, y) from the positive x axis.",
+            "Math::atan2 ~ (1, 1)",
+            atan2,
+        )
+        .function("min", "Math::min ~ (numbers...)", "Smallest of the numbers.", "Math::min ~ (3, 1.5, 2)", min)
+        .function("max", "Math::max ~ (numbers...)", "Largest of the numbers.", "Math::max ~ (3, 1.5, 2)", max)
+        .function(
+            "clamp",
+            "Math::clamp ~ (number, low, high)",
+            "Number limited to the range from low to high.",
+            "Math::clamp ~ (15, 0, 10)",
+            clamp,
+        )
+        .function(
+            "div",
+            "Math::div ~ (dividend, divisor)",
+            "Quotient rounded toward negative infinity.",
+            "Math::div ~ (0 - 7, 2)",
+            div,
+        )
+        .function(
+            "mod",
+            "Math::mod ~ (dividend, divisor)",
+            "Remainder of floored division, taking the sign of the divisor.",
+            "Math::mod ~ (0 - 7, 2)",
+            modulo,
+        )
+        .function(
+            "div_rem",
+            "Math::div_rem ~ (dividend, divisor)",
+            "Quotient and remainder of floored division.",
+            "Math::div_rem ~ (7, 2)",
+            div_rem,
+        )
+}
+
+fn as_float(number: SimpleNumber) -> f64 {
+    match number {
+        SimpleNumber::Integer(i) => i as f64,
+        SimpleNumber::Float(f) => f,
+    }
+}
+
+// all arguments as numbers, None if any aren't numbers
+fn numbers(input: usize, data: &SimpleGarnishData) -> Option<Vec<SimpleNumber>> {
+    arguments(input, data).iter().map(|addr| number_of(*addr, data)).collect()
+}
+
+fn add_result(number: Option<SimpleNumber>, data: &mut SimpleGarnishData) -> NativeResult {
+    match number {
+        Some(number) => Ok(data.add_number(number)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+// integers are returned as is, floats are rounded to an integer when it fits
+fn to_integer(input: usize, data: &mut SimpleGarnishData, f: fn(f64) -> f64) -> NativeResult {
+    let result = number_of(input, data).map(|number| match number {
+        SimpleNumber::Integer(i) => SimpleNumber::Integer(i),
+        SimpleNumber::Float(value) => {
+            let rounded = f(value);
+            match rounded >= i32::MIN as f64 && rounded <= i32::MAX as f64 {
+                true => SimpleNumber::Integer(rounded as i32),
+                false => SimpleNumber::Float(rounded),
+            }
+        }
+    });
+
+    add_result(result, data)
+}
+
+// always produces a float, integers are converted first
+fn float_function(input: usize, data: &mut SimpleGarnishData, f: fn(f64) -> f64) -> NativeResult {
+    let result = number_of(input, data).map(|number| SimpleNumber::Float(f(as_float(number))));
+    add_result(result, data)
+}
+
+fn abs(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = number_of(input, data).and_then(|number| match number {
+        SimpleNumber::Integer(i) => i.checked_abs().map(SimpleNumber::Integer),
+        SimpleNumber::Float(f) => Some(SimpleNumber::Float(f.abs())),
+    });
+
+    add_result(result, data)
+}
+
+fn floor(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    to_integer(input, data, f64::floor)
+}
+
+fn ceil(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    to_integer(input, data, f64::ceil)
+}
+
+fn round(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    to_integer(input, data, f64::round)
+}
+
+fn sqrt(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::sqrt)
+}
+
+fn exp(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::exp)
+}
+
+fn sin(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::sin)
+}
+
+fn cos(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::cos)
+}
+
+fn tan(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::tan)
+}
+
+fn asin(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::asin)
+}
+
+fn acos(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::acos)
+}
+
+fn atan(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    float_function(input, data, f64::atan)
+}
+
+fn atan2(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = match numbers(input, data).as_deref() {
+        Some([y, x]) => Some(SimpleNumber::Float(as_float(*y).atan2(as_float(*x)))),
+        _ => None,
+    };
+
+    add_result(result, data)
+}
+
+// integer powers stay integers unless they overflow
+fn pow(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = match numbers(input, data).as_deref() {
+        Some([SimpleNumber::Integer(base), SimpleNumber::Integer(exponent)]) if *exponent >= 0 => {
+            match base.checked_pow(*exponent as u32) {
+                Some(value) => Some(SimpleNumber::Integer(value)),
+                None => Some(SimpleNumber::Float((*base as f64).powf(*exponent as f64))),
+            }
+        }
+        Some([base, exponent]) => Some(SimpleNumber::Float(as_float(*base).powf(as_float(*exponent)))),
+        _ => None,
+    };
+
+    add_result(result, data)
+}
+
+// natural log of a number, or log of (value, base)
+fn log(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = match numbers(input, data).as_deref() {
+        Some([value]) => Some(SimpleNumber::Float(as_float(*value).ln())),
+        Some([value, base]) => Some(SimpleNumber::Float(as_float(*value).log(as_float(*base)))),
+        _ => None,
+    };
+
+    add_result(result, data)
+}
+
+// compared by value, the chosen number keeps its type
+fn extreme(input: usize, data: &mut SimpleGarnishData, replace: fn(f64, f64) -> bool) -> NativeResult {
+    let result = numbers(input, data).and_then(|numbers| {
+        numbers
+            .into_iter()
+            .fold(None, |current: Option<SimpleNumber>, number| match current {
+                Some(current) if !replace(as_float(current), as_float(number)) => Some(current),
+                _ => Some(number),
+            })
+    });
+
+    add_result(result, data)
+}
+
+fn min(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    extreme(input, data, |current, number| number < current)
+}
+
+fn max(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    extreme(input, data, |current, number| number > current)
+}
+
+fn clamp(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = match numbers(input, data).as_deref() {
+        Some([value, low, high]) if as_float(*low) <= as_float(*high) => {
+            if as_float(*value) < as_float(*low) {
+                Some(*low)
+            } else if as_float(*value) > as_float(*high) {
+                Some(*high)
+            } else {
+                Some(*value)
+            }
+        }
+        _ => None,
+    };
+
+    add_result(result, data)
+}
+
+// division rounding toward negative infinity, with the remainder taking the sign of the divisor
+fn floored_division(input: usize, data: &SimpleGarnishData) -> Option<(SimpleNumber, SimpleNumber)> {
+    match numbers(input, data).as_deref() {
+        Some([SimpleNumber::Integer(a), SimpleNumber::Integer(b)]) => {
+            let quotient = a.checked_div_euclid(*b)?;
+            let remainder = a.checked_rem_euclid(*b)?;
+            // euclidean division rounds toward negative infinity only for positive divisors
+            let (quotient, remainder) = match *b < 0 && remainder != 0 {
+                true => (quotient.checked_sub(1)?, remainder.checked_add(*b)?),
+                false => (quotient, remainder),
+            };
+            Some((SimpleNumber::Integer(quotient), SimpleNumber::Integer(remainder)))
+        }
+        Some([a, b]) if as_float(*b) != 0.0 => {
+            let (a, b) = (as_float(*a), as_float(*b));
+            let quotient = (a / b).floor();
+            Some((SimpleNumber::Float(quotient), SimpleNumber::Float(a - b * quotient)))
+        }
+        _ => None,
+    }
+}
+
+fn div(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = floored_division(input, data).map(|(quotient, _)| quotient);
+    add_result(result, data)
+}
+
+fn modulo(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = floored_division(input, data).map(|(_, remainder)| remainder);
+    add_result(result, data)
+}
+
+fn div_rem(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match floored_division(input, data) {
+        Some((quotient, remainder)) => {
+            let items = [data.add_number(quotient)?, data.add_number(remainder)?];
+            Ok(add_list(&items, data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_error(), None);
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(result_of("Math::E"), Some(std::f64::consts::E.to_string()));
+        assert_eq!(result_of("Math::Tau == Math::PI * 2"), Some("True".to_string()));
+        assert_eq!(result_of("Math::Infinity"), Some("inf".to_string()));
+        assert_eq!(result_of("Math::NaN"), Some("NaN".to_string()));
+    }
+
+    #[test]
+    fn abs() {
+        assert_eq!(result_of("Math::abs ~ (0 - 5)"), Some("5".to_string()));
+        assert_eq!(result_of("Math::abs ~ (0 - 2.5)"), Some("2.5".to_string()));
+        assert_eq!(result_of("Math::abs ~ Math::IntegerMin"), Some("()".to_string()));
+        assert_eq!(result_of("Math::abs ~ \"5\""), Some("()".to_string()));
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(result_of("Math::floor ~ 2.7"), Some("2".to_string()));
+        assert_eq!(result_of("Math::floor ~ (0 - 2.5)"), Some("-3".to_string()));
+        assert_eq!(result_of("Math::ceil ~ 2.1"), Some("3".to_string()));
+        assert_eq!(result_of("Math::round ~ 2.5"), Some("3".to_string()));
+        assert_eq!(result_of("Math::round ~ 7"), Some("7".to_string()));
+        assert_eq!(result_of("Math::floor ~ Math::Infinity"), Some("inf".to_string()));
+    }
+
+    #[test]
+    fn sqrt_and_exp() {
+        assert_eq!(result_of("Math::sqrt ~ 16"), Some("4".to_string()));
+        assert_eq!(result_of("Math::sqrt ~ 2.25"), Some("1.5".to_string()));
+        assert_eq!(result_of("Math::exp ~ 0"), Some("1".to_string()));
+    }
+
+    #[test]
+    fn pow() {
+        assert_eq!(result_of("Math::pow ~ (2, 10)"), Some("1024".to_string()));
+        assert_eq!(result_of("Math::pow ~ (4, 0.5)"), Some("2".to_string()));
+        assert_eq!(result_of("Math::pow ~ (2, 0 - 1)"), Some("0.5".to_string()));
+        assert_eq!(result_of("Math::pow ~ (2, 40)"), Some("1099511627776".to_string()));
+    }
+
+    #[test]
+    fn log() {
+        assert_eq!(result_of("Math::log ~ Math::E"), Some("1".to_string()));
+        assert_eq!(result_of("Math::log ~ (8, 2)"), Some("3".to_string()));
+    }
+
+    #[test]
+    fn trigonometry() {
+        assert_eq!(result_of("Math::sin ~ 0"), Some("0".to_string()));
+        assert_eq!(result_of("Math::cos ~ 0"), Some("1".to_string()));
+        assert_eq!(result_of("Math::tan ~ 0"), Some("0".to_string()));
+        assert_eq!(result_of("Math::asin ~ 1"), Some((std::f64::consts::PI / 2.0).to_string()));
+        assert_eq!(result_of("Math::acos ~ 1"), Some("0".to_string()));
+        assert_eq!(result_of("Math::atan ~ 0"), Some("0".to_string()));
+        assert_eq!(result_of("Math::atan2 ~ (1, 0)"), Some((std::f64::consts::PI / 2.0).to_string()));
+    }
+
+    #[test]
+    fn min_and_max() {
+        assert_eq!(result_of("Math::min ~ (3, 1.5, 2)"), Some("1.5".to_string()));
+        assert_eq!(result_of("Math::max ~ (3, 1.5, 2)"), Some("3".to_string()));
+        assert_eq!(result_of("Math::max ~ 4"), Some("4".to_string()));
+        assert_eq!(result_of("Math::max ~ (4, \"5\")"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn clamp() {
+        assert_eq!(result_of("Math::clamp ~ (15, 0, 10)"), Some("10".to_string()));
+        assert_eq!(result_of("Math::clamp ~ (0 - 1, 0, 10)"), Some("0".to_string()));
+        assert_eq!(result_of("Math::clamp ~ (2.5, 0, 10)"), Some("2.5".to_string()));
+        assert_eq!(result_of("Math::clamp ~ (5, 10, 0)"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn integer_division() {
+        assert_eq!(result_of("Math::div ~ (7, 2)"), Some("3".to_string()));
+        assert_eq!(result_of("Math::div ~ (0 - 7, 2)"), Some("-4".to_string()));
+        assert_eq!(result_of("Math::div ~ (7, 0 - 2)"), Some("-4".to_string()));
+        assert_eq!(result_of("Math::mod ~ (0 - 7, 2)"), Some("1".to_string()));
+        assert_eq!(result_of("Math::mod ~ (7, 0 - 2)"), Some("-1".to_string()));
+        assert_eq!(result_of("Math::div_rem ~ (7, 2)"), Some("3, 1".to_string()));
+        assert_eq!(result_of("Math::div ~ (7.5, 2)"), Some("3".to_string()));
+        assert_eq!(result_of("Math::div ~ (7, 0)"), Some("()".to_string()));
+    }
+}
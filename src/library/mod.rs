@@ -0,0 +1,115 @@
+use crate::catalog::Module;
+use crate::context::BrowserContext;
+use garnish_lang::simple::{symbol_value, DataError, SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::{GarnishData, RuntimeError};
+use garnish_lang_utilities::iterate_concatentation;
+
+mod json;
+mod list;
+mod math;
+pub mod random;
+mod regex;
+mod string;
+pub mod time;
+
+pub type NativeResult = Result<usize, RuntimeError<DataError>>;
+
+/// Function implemented in Rust, resolved by name and applied like an expression, `Str::length ~ "text"`.
+///
+/// Receives the address of the input and returns the address of its result.
+/// Input that doesn't match what the function expects results in unit, like other operations on invalid types.
+pub type NativeFunction = fn(&mut BrowserContext, usize, &mut SimpleGarnishData) -> NativeResult;
+
+/// Catalog of all built in namespaces.
+pub fn modules() -> Vec<Module> {
+    vec![
+        json::module(),
+        list::module(),
+        math::module(),
+        random::module(),
+        regex::module(),
+        string::module(),
+        time::module(),
+    ]
+}
+
+/// Text of a character list, a single character or a concatenation of them.
+pub fn text_of(addr: usize, data: &SimpleGarnishData) -> Option<String> {
+    match data.get_data().get(addr)? {
+        SimpleData::CharList(text) => Some(text.clone()),
+        SimpleData::Char(c) => Some(c.to_string()),
+        SimpleData::Concatenation(..) => {
+            let mut items = vec![];
+            iterate_concatentation(addr, data, |item| items.push(item)).ok()?;
+            items.iter().map(|item| text_of(*item, data)).collect()
+        }
+        _ => None,
+    }
+}
+
+pub fn number_of(addr: usize, data: &SimpleGarnishData) -> Option<SimpleNumber> {
+    match data.get_data().get(addr)? {
+        SimpleData::Number(number) => Some(*number),
+        _ => None,
+    }
+}
+
+pub fn integer_of(addr: usize, data: &SimpleGarnishData) -> Option<i32> {
+    match number_of(addr, data)? {
+        SimpleNumber::Integer(i) => Some(i),
+        SimpleNumber::Float(_) => None,
+    }
+}
+
+/// Items of a list, in order.
+pub fn list_items(addr: usize, data: &SimpleGarnishData) -> Option<Vec<usize>> {
+    match data.get_data().get(addr)? {
+        SimpleData::List(items, _) => Some(items.clone()),
+        _ => None,
+    }
+}
+
+/// Items of the input list for functions taking multiple arguments, `Str::split ~ ("a,b", ",")`.
+/// Any other input is a single argument.
+pub fn arguments(addr: usize, data: &SimpleGarnishData) -> Vec<usize> {
+    list_items(addr, data).unwrap_or_else(|| vec![addr])
+}
+
+pub fn add_text(text: &str, data: &mut SimpleGarnishData) -> Result<usize, DataError> {
+    data.start_char_list()?;
+    for c in text.chars() {
+        data.add_to_char_list(c)?;
+    }
+    data.end_char_list()
+}
+
+/// Symbol with its name kept in the data, so names made at runtime only last as long as the data.
+pub fn add_named_symbol(name: &str, data: &mut SimpleGarnishData) -> Result<usize, DataError> {
+    let symbol = symbol_value(name);
+    data.get_data_mut().insert_symbol(symbol, name);
+    data.add_symbol(symbol)
+}
+
+pub fn add_integer(value: i32, data: &mut SimpleGarnishData) -> Result<usize, DataError> {
+    data.add_number(SimpleNumber::Integer(value))
+}
+
+pub fn add_bool(value: bool, data: &mut SimpleGarnishData) -> Result<usize, DataError> {
+    match value {
+        true => data.add_true(),
+        false => data.add_false(),
+    }
+}
+
+pub fn add_list(items: &[usize], data: &mut SimpleGarnishData) -> Result<usize, DataError> {
+    data.start_list(items.len())?;
+    for item in items {
+        // same as lists made by the runtime, pairs with a symbol on the left are associations
+        let is_association = match data.get_data().get(*item) {
+            Some(SimpleData::Pair(left, _)) => matches!(data.get_data().get(*left), Some(SimpleData::Symbol(_))),
+            _ => false,
+        };
+        data.add_to_list(*item, is_association)?;
+    }
+    data.end_list()
+}
@@ -0,0 +1,198 @@
+use crate::catalog::{Capabilities, Module};
+use crate::context::BrowserContext;
+use crate::library::{add_list, arguments, integer_of, list_items, number_of, NativeResult};
+use garnish_lang::simple::{SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::GarnishData;
+
+/// SplitMix64 generator, produces the same sequence for a seed on every platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Float in the range [0, 1).
+    pub fn next_float(&mut self) -> f64 {
+        // top 53 bits fill the float's mantissa
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Index in the range [0, len), len must be greater than zero.
+    pub fn next_index(&mut self, len: u64) -> u64 {
+        ((self.next_u64() as u128 * len as u128) >> 64) as u64
+    }
+}
+
+pub fn module() -> Module {
+    Module::new("Random", "Random values from a generator seeded for each run.")
+        .requires(Capabilities::Deterministic)
+        .function(
+            "int",
+            "Random::int ~ (low, high)",
+            "Integer from low to high, both inclusive.",
+            "Random::int ~ (1, 6)",
+            int,
+        )
+        .function(
+            "float",
+            "Random::float ~ () | (low, high)",
+            "Float from 0 up to 1, or from low up to high.",
+            "Random::float ~ ()",
+            float,
+        )
+        .function("choice", "Random::choice ~ list", "One of the list's items.", "Random::choice ~ (\"a\", \"b\", \"c\")", choice)
+        .function("shuffle", "Random::shuffle ~ list", "Items in a random order.", "Random::shuffle ~ (1, 2, 3, 4)", shuffle)
+}
+
+// integer in (low, high), both inclusive
+fn int(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let bounds = arguments(input, data)
+        .iter()
+        .map(|addr| integer_of(*addr, data))
+        .collect::<Option<Vec<i32>>>();
+
+    match bounds.as_deref() {
+        Some([low, high]) if low <= high => {
+            let span = (*high as i64 - *low as i64 + 1) as u64;
+            let value = *low as i64 + context.random().next_index(span) as i64;
+            Ok(data.add_number(SimpleNumber::Integer(value as i32))?)
+        }
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+// float in [0, 1) when given unit, or in [low, high) when given (low, high)
+fn float(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let as_float = |number: SimpleNumber| match number {
+        SimpleNumber::Integer(i) => i as f64,
+        SimpleNumber::Float(f) => f,
+    };
+
+    let (low, high) = match data.get_data().get(input) {
+        Some(SimpleData::Unit) => (0.0, 1.0),
+        _ => match arguments(input, data).as_slice() {
+            [low, high] => match (number_of(*low, data), number_of(*high, data)) {
+                (Some(low), Some(high)) if as_float(low) <= as_float(high) => (as_float(low), as_float(high)),
+                _ => return Ok(data.add_unit()?),
+            },
+            _ => return Ok(data.add_unit()?),
+        },
+    };
+
+    let value = low + context.random().next_float() * (high - low);
+    Ok(data.add_number(SimpleNumber::Float(value))?)
+}
+
+fn choice(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match list_items(input, data) {
+        Some(items) if !items.is_empty() => {
+            let index = context.random().next_index(items.len() as u64) as usize;
+            Ok(items[index])
+        }
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+fn shuffle(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match list_items(input, data) {
+        Some(mut items) => {
+            // Fisher-Yates
+            for i in (1..items.len()).rev() {
+                let j = context.random().next_index(i as u64 + 1) as usize;
+                items.swap(i, j);
+            }
+            Ok(add_list(&items, data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::library::random::Generator;
+    use crate::script::GarnishScript;
+
+    fn results_with_seed(source: &str, seed: u32, count: usize) -> Vec<Option<String>> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.set_random_seed(Some(seed));
+        script.compile();
+
+        (0..count)
+            .map(|i| {
+                script.execute();
+                script.get_execution_result(i)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generator_sequence() {
+        // reference values for SplitMix64 with a seed of 0
+        let mut generator = Generator::new(0);
+        assert_eq!(generator.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(generator.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+    }
+
+    #[test]
+    fn same_seed_same_results() {
+        let source = "Random::int ~ (1, 1000), Random::float ~ (), Random::shuffle ~ (1, 2, 3, 4, 5)";
+        let first = results_with_seed(source, 42, 2);
+
+        assert_eq!(first[0], first[1]);
+        assert_eq!(first, results_with_seed(source, 42, 2));
+        assert_ne!(first, results_with_seed(source, 43, 2));
+    }
+
+    #[test]
+    fn int_in_range() {
+        let source = "List::map ~ (List::range ~ 200, { Random::int ~ (0 - 2, 2) })";
+        let result = results_with_seed(source, 7, 1)[0].clone().unwrap();
+        let values: Vec<i32> = result.split(", ").map(|v| v.parse().unwrap()).collect();
+
+        assert!(values.iter().all(|v| (-2..=2).contains(v)));
+        for expected in -2..=2 {
+            assert!(values.contains(&expected));
+        }
+
+        assert_eq!(results_with_seed("Random::int ~ (5, 1)", 7, 1)[0], Some("()".to_string()));
+        assert_eq!(results_with_seed("Random::int ~ (3, 3)", 7, 1)[0], Some("3".to_string()));
+    }
+
+    #[test]
+    fn float_in_range() {
+        let source = "List::map ~ (List::range ~ 100, { Random::float ~ (10, 20) })";
+        let result = results_with_seed(source, 7, 1)[0].clone().unwrap();
+
+        assert!(result
+            .split(", ")
+            .map(|v| v.parse::<f64>().unwrap())
+            .all(|v| (10.0..20.0).contains(&v)));
+    }
+
+    #[test]
+    fn choice() {
+        let result = results_with_seed("Random::choice ~ (\"a\", \"b\", \"c\")", 7, 1)[0].clone();
+
+        assert!(["\"a\"", "\"b\"", "\"c\""].contains(&result.unwrap().as_str()));
+        assert_eq!(results_with_seed("Random::choice ~ 5", 7, 1)[0], Some("()".to_string()));
+    }
+
+    #[test]
+    fn shuffle() {
+        let result = results_with_seed("List::sort ~ (Random::shuffle ~ (List::range ~ 10))", 7, 1)[0].clone();
+
+        assert_eq!(result, Some("0, 1, 2, 3, 4, 5, 6, 7, 8, 9".to_string()));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::catalog::Module;
+use crate::context::BrowserContext;
+use crate::library::{add_bool, add_list, add_named_symbol, add_text, arguments, text_of, NativeResult};
+use garnish_lang::simple::{DataError, SimpleGarnishData};
+use garnish_lang::{GarnishData, RuntimeError};
+use regex::Regex;
+
+pub fn module() -> Module {
+    Module::new("Regex", "Regular expression matching on character lists.")
+        .function(
+            "is_match",
+            "Regex::is_match ~ (text, pattern)",
+            "Whether the pattern matches anywhere in the text.",
+            "Regex::is_match ~ (\"abc123\", \"\\\\d+\")",
+            is_match,
+        )
+        .function(
+            "find",
+            "Regex::find ~ (text, pattern)",
+            "First text matching the pattern.",
+            "Regex::find ~ (\"order 42\", \"\\\\d+\")",
+            find,
+        )
+        .function(
+            "find_all",
+            "Regex::find_all ~ (text, pattern)",
+            "Every text matching the pattern.",
+            "Regex::find_all ~ (\"1, 22, 333\", \"\\\\d+\")",
+            find_all,
+        )
+        .function(
+            "captures",
+            "Regex::captures ~ (text, pattern)",
+            "Groups of the first match starting with the whole match, named groups are associations.",
+            "(Regex::captures ~ (\"key=value\", \"(?P<key>\\\\w+)=(?P<value>\\\\w+)\")).value",
+            captures,
+        )
+        .function(
+            "replace",
+            "Regex::replace ~ (text, pattern, replacement)",
+            "Text with every match replaced, `$1` and `$name` refer to groups.",
+            "Regex::replace ~ (\"john smith\", \"(\\\\w+) (\\\\w+)\", \"$2, $1\")",
+            replace,
+        )
+}
+
+// (text, pattern) followed by any other text arguments, None if any aren't character lists
+// an invalid pattern is an error instead of unit so a typo doesn't quietly fail every match
+fn with_pattern<'a>(
+    context: &'a mut BrowserContext,
+    input: usize,
+    data: &SimpleGarnishData,
+) -> Result<Option<(&'a Regex, Vec<String>)>, RuntimeError<DataError>> {
+    let texts = arguments(input, data)
+        .iter()
+        .map(|addr| text_of(*addr, data))
+        .collect::<Option<Vec<String>>>();
+
+    match texts {
+        Some(mut texts) if texts.len() >= 2 => {
+            let pattern = texts.remove(1);
+            let regex = context
+                .pattern(&pattern)
+                .map_err(|e| RuntimeError::new_message(format!("Invalid pattern `{}`: {}", pattern, e)))?;
+            Ok(Some((regex, texts)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn is_match(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match with_pattern(context, input, data)? {
+        Some((regex, texts)) if texts.len() == 1 => Ok(add_bool(regex.is_match(&texts[0]), data)?),
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+// first matching text, unit when there isn't one
+fn find(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let found = match with_pattern(context, input, data)? {
+        Some((regex, texts)) if texts.len() == 1 => regex.find(&texts[0]).map(|m| m.as_str().to_string()),
+        _ => None,
+    };
+
+    match found {
+        Some(text) => Ok(add_text(&text, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn find_all(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let found = match with_pattern(context, input, data)? {
+        Some((regex, texts)) if texts.len() == 1 => regex
+            .find_iter(&texts[0])
+            .map(|m| m.as_str().to_string())
+            .collect::<Vec<String>>(),
+        _ => return Ok(data.add_unit()?),
+    };
+
+    let items = found
+        .iter()
+        .map(|text| add_text(text, data))
+        .collect::<Result<Vec<usize>, _>>()?;
+    Ok(add_list(&items, data)?)
+}
+
+// groups of the first match starting with the whole match, named groups are associations
+// and groups that didn't participate are unit
+fn captures(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let groups = match with_pattern(context, input, data)? {
+        Some((regex, texts)) if texts.len() == 1 => regex.captures(&texts[0]).map(|captures| {
+            regex
+                .capture_names()
+                .zip(captures.iter())
+                .map(|(name, group)| (name.map(str::to_string), group.map(|m| m.as_str().to_string())))
+                .collect::<Vec<_>>()
+        }),
+        _ => None,
+    };
+
+    let groups = match groups {
+        Some(groups) => groups,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let mut items = vec![];
+    for (name, group) in groups {
+        let value = match group {
+            Some(text) => add_text(&text, data)?,
+            None => data.add_unit()?,
+        };
+
+        items.push(match name {
+            Some(name) => {
+                let symbol = add_named_symbol(&name, data)?;
+                data.add_pair((symbol, value))?
+            }
+            None => value,
+        });
+    }
+
+    Ok(add_list(&items, data)?)
+}
+
+// (text, pattern, replacement) replacing every match, `$1` and `$name` refer to groups
+fn replace(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let replaced = match with_pattern(context, input, data)? {
+        Some((regex, texts)) if texts.len() == 2 => Some(regex.replace_all(&texts[0], texts[1].as_str()).into_owned()),
+        _ => None,
+    };
+
+    match replaced {
+        Some(text) => Ok(add_text(&text, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::BrowserContext;
+    use crate::script::GarnishScript;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_error(), None);
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn is_match() {
+        assert_eq!(result_of(r#"Regex::is_match ~ ("abc123", "^[a-z]+\\d+$")"#), Some("True".to_string()));
+        assert_eq!(result_of(r#"Regex::is_match ~ ("abc", "\\d")"#), Some("False".to_string()));
+        assert_eq!(result_of(r#"Regex::is_match ~ (5, "\\d")"#), Some("()".to_string()));
+    }
+
+    #[test]
+    fn find() {
+        assert_eq!(result_of(r#"Regex::find ~ ("order 42 of 100", "\\d+")"#), Some("\"42\"".to_string()));
+        assert_eq!(result_of(r#"Regex::find ~ ("none", "\\d+")"#), Some("()".to_string()));
+    }
+
+    #[test]
+    fn find_all() {
+        assert_eq!(
+            result_of(r#"Regex::find_all ~ ("order 42 of 100", "\\d+")"#),
+            Some("\"42\", \"100\"".to_string())
+        );
+        assert_eq!(result_of(r#"Regex::find_all ~ ("none", "\\d+")"#), Some("(,)".to_string()));
+    }
+
+    #[test]
+    fn captures() {
+        assert_eq!(
+            result_of(r#"Regex::captures ~ ("2024-03", "(\\d+)-(\\d+)")"#),
+            Some("\"2024-03\", \"2024\", \"03\"".to_string())
+        );
+        assert_eq!(
+            result_of(r#"(Regex::captures ~ ("key=value", "(?P<key>\\w+)=(?P<value>\\w+)")).value"#),
+            Some("\"value\"".to_string())
+        );
+        assert_eq!(
+            result_of(r#"Regex::captures ~ ("a", "(a)|(b)")"#),
+            Some("\"a\", \"a\", ()".to_string())
+        );
+        assert_eq!(result_of(r#"Regex::captures ~ ("c", "(a)|(b)")"#), Some("()".to_string()));
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(
+            result_of(r##"Regex::replace ~ ("a1b22c333", "\\d+", "#")"##),
+            Some("\"a#b#c#\"".to_string())
+        );
+        assert_eq!(
+            result_of(r#"Regex::replace ~ ("john smith", "(\\w+) (\\w+)", "$2, $1")"#),
+            Some("\"smith, john\"".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let mut script = GarnishScript::new("main".to_string(), r#"Regex::is_match ~ ("abc", "(")"#.to_string());
+        script.compile();
+        script.execute();
+
+        assert!(script.get_error().unwrap().contains("Invalid pattern `(`"));
+    }
+
+    #[test]
+    fn patterns_cached() {
+        let mut context = BrowserContext::new();
+        let first = context.pattern("\\d+").unwrap() as *const _;
+        let second = context.pattern("\\d+").unwrap() as *const _;
+
+        assert_eq!(first, second);
+        assert!(context.pattern("(").is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::catalog::Module;
+use crate::context::BrowserContext;
+use crate::library::{
+    add_bool, add_integer, add_list, add_text, arguments, integer_of, list_items, text_of, NativeResult,
+};
+use garnish_lang::simple::{DataError, SimpleGarnishData};
+use garnish_lang::{GarnishData, RuntimeError};
+
+const MAX_TEXT_LENGTH: usize = 100_000;
+
+pub fn module() -> Module {
+    Module::new("Str", "Functions on character lists.")
+        .function("length", "Str::length ~ text", "Number of characters.", "Str::length ~ \"garnish\"", length)
+        .function("upper", "Str::upper ~ text", "Text in upper case.", "Str::upper ~ \"garnish\"", upper)
+        .function("lower", "Str::lower ~ text", "Text in lower case.", "Str::lower ~ \"Garnish\"", lower)
+        .function("trim", "Str::trim ~ text", "Text without leading and trailing whitespace.", "Str::trim ~ \"  garnish  \"", trim)
+        .function(
+            "split",
+            "Str::split ~ (text, separator)",
+            "Parts of the text between each separator.",
+            "Str::split ~ (\"a,b,c\", \",\")",
+            split,
+        )
+        .function(
+            "join",
+            "Str::join ~ (list, separator)",
+            "Text of the list's items with the separator between them.",
+            "Str::join ~ ((\"a\", \"b\", \"c\"), \"-\")",
+            join,
+        )
+        .function(
+            "replace",
+            "Str::replace ~ (text, from, to)",
+            "Text with every occurrence of from replaced.",
+            "Str::replace ~ (\"one two one\", \"one\", \"three\")",
+            replace,
+        )
+        .function(
+            "contains",
+            "Str::contains ~ (text, part)",
+            "Whether the part appears in the text.",
+            "Str::contains ~ (\"garnish\", \"nis\")",
+            contains,
+        )
+        .function(
+            "starts_with",
+            "Str::starts_with ~ (text, part)",
+            "Whether the text begins with the part.",
+            "Str::starts_with ~ (\"garnish\", \"gar\")",
+            starts_with,
+        )
+        .function(
+            "ends_with",
+            "Str::ends_with ~ (text, part)",
+            "Whether the text ends with the part.",
+            "Str::ends_with ~ (\"garnish\", \"nish\")",
+            ends_with,
+        )
+        .function(
+            "pad_start",
+            "Str::pad_start ~ (text, width) | (text, width, fill)",
+            "Text padded at the start to the width, with spaces or the fill. At most 100000 characters.",
+            "Str::pad_start ~ (\"5\", 3, \"0\")",
+            pad_start,
+        )
+        .function(
+            "pad_end",
+            "Str::pad_end ~ (text, width) | (text, width, fill)",
+            "Text padded at the end to the width, with spaces or the fill. At most 100000 characters.",
+            "Str::pad_end ~ (\"ab\", 5, \"-\")",
+            pad_end,
+        )
+        .function(
+            "repeat",
+            "Str::repeat ~ (text, count)",
+            "Text repeated the number of times. At most 100000 characters.",
+            "Str::repeat ~ (\"ab\", 3)",
+            repeat,
+        )
+        .function(
+            "char_codes",
+            "Str::char_codes ~ text",
+            "Unicode code point of each character.",
+            "Str::char_codes ~ \"AZ\"",
+            char_codes,
+        )
+        .function(
+            "from_char_codes",
+            "Str::from_char_codes ~ (codes...)",
+            "Text of the characters with the given code points.",
+            "Str::from_char_codes ~ (104, 105)",
+            from_char_codes,
+        )
+}
+
+// all arguments as text, None if any aren't character lists
+fn texts(input: usize, data: &SimpleGarnishData) -> Option<Vec<String>> {
+    arguments(input, data).iter().map(|addr| text_of(*addr, data)).collect()
+}
+
+fn map_text(input: usize, data: &mut SimpleGarnishData, f: fn(&str) -> String) -> NativeResult {
+    match text_of(input, data) {
+        Some(text) => Ok(add_text(&f(&text), data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn test_texts(input: usize, data: &mut SimpleGarnishData, f: fn(&str, &str) -> bool) -> NativeResult {
+    match texts(input, data).as_deref() {
+        Some([text, part]) => Ok(add_bool(f(text, part), data)?),
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+fn length(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match text_of(input, data) {
+        Some(text) => Ok(add_integer(text.chars().count() as i32, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn upper(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    map_text(input, data, str::to_uppercase)
+}
+
+fn lower(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    map_text(input, data, str::to_lowercase)
+}
+
+fn trim(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    map_text(input, data, |text| text.trim().to_string())
+}
+
+fn split(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match texts(input, data).as_deref() {
+        Some([text, separator]) => {
+            let parts = text
+                .split(separator.as_str())
+                .map(|part| add_text(part, data))
+                .collect::<Result<Vec<usize>, _>>()?;
+            Ok(add_list(&parts, data)?)
+        }
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+fn join(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let parts = match arguments(input, data).as_slice() {
+        [list, separator] => list_items(*list, data)
+            .and_then(|items| items.iter().map(|item| text_of(*item, data)).collect::<Option<Vec<String>>>())
+            .zip(text_of(*separator, data)),
+        _ => None,
+    };
+
+    match parts {
+        Some((parts, separator)) => Ok(add_text(&parts.join(&separator), data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn replace(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match texts(input, data).as_deref() {
+        Some([text, from, to]) => Ok(add_text(&text.replace(from.as_str(), to), data)?),
+        _ => Ok(data.add_unit()?),
+    }
+}
+
+fn contains(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    test_texts(input, data, |text, part| text.contains(part))
+}
+
+fn starts_with(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    test_texts(input, data, |text, part| text.starts_with(part))
+}
+
+fn ends_with(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    test_texts(input, data, |text, part| text.ends_with(part))
+}
+
+fn check_length(function: &str, length: usize) -> Result<(), RuntimeError<DataError>> {
+    match length > MAX_TEXT_LENGTH {
+        true => Err(RuntimeError::new_message(format!(
+            "{} of {} characters is over the limit of {}",
+            function, length, MAX_TEXT_LENGTH
+        ))),
+        false => Ok(()),
+    }
+}
+
+// (text, width) padded with spaces, or (text, width, fill)
+fn padding(
+    function: &str,
+    input: usize,
+    data: &SimpleGarnishData,
+) -> Result<Option<(String, String)>, RuntimeError<DataError>> {
+    let arguments = match arguments(input, data).as_slice() {
+        [text, width] => text_of(*text, data).zip(integer_of(*width, data)).map(|(t, w)| (t, w, " ".to_string())),
+        [text, width, fill] => text_of(*text, data)
+            .zip(integer_of(*width, data))
+            .zip(text_of(*fill, data))
+            .map(|((t, w), f)| (t, w, f)),
+        _ => None,
+    };
+
+    let (text, width, fill) = match arguments {
+        Some(arguments) if !arguments.2.is_empty() => arguments,
+        _ => return Ok(None),
+    };
+
+    let missing = (width.max(0) as usize).saturating_sub(text.chars().count());
+    if missing > 0 {
+        check_length(function, width as usize)?;
+    }
+
+    let padding = fill.chars().cycle().take(missing).collect();
+    Ok(Some((text, padding)))
+}
+
+fn pad_start(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match padding("Str::pad_start", input, data)? {
+        Some((text, padding)) => Ok(add_text(&format!("{}{}", padding, text), data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn pad_end(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match padding("Str::pad_end", input, data)? {
+        Some((text, padding)) => Ok(add_text(&format!("{}{}", text, padding), data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn repeat(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let arguments = match arguments(input, data).as_slice() {
+        [text, count] => text_of(*text, data).zip(integer_of(*count, data)).filter(|(_, count)| *count >= 0),
+        _ => None,
+    };
+
+    match arguments {
+        Some((text, count)) => {
+            check_length("Str::repeat", text.chars().count().saturating_mul(count as usize))?;
+            Ok(add_text(&text.repeat(count as usize), data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn char_codes(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match text_of(input, data) {
+        Some(text) => {
+            let codes = text
+                .chars()
+                .map(|c| add_integer(c as i32, data))
+                .collect::<Result<Vec<usize>, _>>()?;
+            Ok(add_list(&codes, data)?)
+        }
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn from_char_codes(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let text = arguments(input, data)
+        .iter()
+        .map(|addr| integer_of(*addr, data).and_then(|code| std::char::from_u32(code as u32)))
+        .collect::<Option<String>>();
+
+    match text {
+        Some(text) => Ok(add_text(&text, data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_error(), None);
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn length() {
+        assert_eq!(result_of("Str::length ~ \"garnish\""), Some("7".to_string()));
+        assert_eq!(result_of("Str::length ~ \"\""), Some("0".to_string()));
+        assert_eq!(result_of("Str::length ~ 5"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn upper_and_lower() {
+        assert_eq!(result_of("Str::upper ~ \"Garnish\""), Some("\"GARNISH\"".to_string()));
+        assert_eq!(result_of("Str::lower ~ \"Garnish\""), Some("\"garnish\"".to_string()));
+    }
+
+    #[test]
+    fn trim() {
+        assert_eq!(result_of("Str::trim ~ \"  spaced  \""), Some("\"spaced\"".to_string()));
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            result_of("Str::split ~ (\"a,b,c\", \",\")"),
+            Some("\"a\", \"b\", \"c\"".to_string())
+        );
+        assert_eq!(result_of("Str::split ~ \"a,b\""), Some("()".to_string()));
+    }
+
+    #[test]
+    fn join() {
+        assert_eq!(
+            result_of("Str::join ~ ((\"a\", \"b\", \"c\"), \"-\")"),
+            Some("\"a-b-c\"".to_string())
+        );
+        assert_eq!(result_of("Str::join ~ ((\"a\", 5), \"-\")"), Some("()".to_string()));
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(
+            result_of("Str::replace ~ (\"one two one\", \"one\", \"three\")"),
+            Some("\"three two three\"".to_string())
+        );
+    }
+
+    #[test]
+    fn contains() {
+        assert_eq!(result_of("Str::contains ~ (\"garnish\", \"nis\")"), Some("True".to_string()));
+        assert_eq!(result_of("Str::contains ~ (\"garnish\", \"xyz\")"), Some("False".to_string()));
+    }
+
+    #[test]
+    fn starts_and_ends_with() {
+        assert_eq!(result_of("Str::starts_with ~ (\"garnish\", \"gar\")"), Some("True".to_string()));
+        assert_eq!(result_of("Str::starts_with ~ (\"garnish\", \"nish\")"), Some("False".to_string()));
+        assert_eq!(result_of("Str::ends_with ~ (\"garnish\", \"nish\")"), Some("True".to_string()));
+        assert_eq!(result_of("Str::ends_with ~ (\"garnish\", \"gar\")"), Some("False".to_string()));
+    }
+
+    #[test]
+    fn pad() {
+        assert_eq!(result_of("Str::pad_start ~ (\"5\", 3, \"0\")"), Some("\"005\"".to_string()));
+        assert_eq!(result_of("Str::pad_start ~ (\"5\", 3)"), Some("\"  5\"".to_string()));
+        assert_eq!(result_of("Str::pad_end ~ (\"ab\", 5, \"-=\")"), Some("\"ab-=-\"".to_string()));
+        assert_eq!(result_of("Str::pad_end ~ (\"long\", 2)"), Some("\"long\"".to_string()));
+    }
+
+    #[test]
+    fn repeat() {
+        assert_eq!(result_of("Str::repeat ~ (\"ab\", 3)"), Some("\"ababab\"".to_string()));
+        assert_eq!(result_of("Str::repeat ~ (\"ab\", 0 - 1)"), Some("()".to_string()));
+    }
+
+    fn error_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.compile();
+        script.execute();
+        script.get_error()
+    }
+
+    #[test]
+    fn output_over_limit() {
+        assert_eq!(
+            error_of("Str::repeat ~ (\"ab\", Math::IntegerMax)"),
+            Some("Str::repeat of 4294967294 characters is over the limit of 100000".to_string())
+        );
+        assert_eq!(
+            error_of("Str::pad_start ~ (\"a\", Math::IntegerMax)"),
+            Some("Str::pad_start of 2147483647 characters is over the limit of 100000".to_string())
+        );
+        assert_eq!(
+            error_of("Str::pad_end ~ (\"a\", 100001, \"-\")"),
+            Some("Str::pad_end of 100001 characters is over the limit of 100000".to_string())
+        );
+        assert_eq!(result_of("Str::length ~ (Str::repeat ~ (\"ab\", 50000))"), Some("100000".to_string()));
+    }
+
+    #[test]
+    fn char_codes() {
+        assert_eq!(result_of("Str::char_codes ~ \"AZ\""), Some("65, 90".to_string()));
+        assert_eq!(result_of("Str::from_char_codes ~ (104, 105)"), Some("\"hi\"".to_string()));
+        assert_eq!(result_of("Str::from_char_codes ~ 65"), Some("\"A\"".to_string()));
+    }
+}
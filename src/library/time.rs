@@ -0,0 +1,538 @@
+use crate::catalog::{Capabilities, Module};
+use crate::context::BrowserContext;
+use crate::library::{add_list, add_text, arguments, number_of, text_of, NativeResult};
+use crate::utils::now_millis;
+use garnish_lang::simple::{symbol_value, SimpleData, SimpleGarnishData, SimpleNumber};
+use garnish_lang::GarnishData;
+
+const MILLIS_PER_SECOND: f64 = 1000.0;
+const MILLIS_PER_MINUTE: f64 = 60.0 * MILLIS_PER_SECOND;
+const MILLIS_PER_HOUR: f64 = 60.0 * MILLIS_PER_MINUTE;
+const MILLIS_PER_DAY: f64 = 24.0 * MILLIS_PER_HOUR;
+
+// years representable by JavaScript dates
+const MAX_YEAR: i64 = 275_760;
+
+const COMPONENTS: [&str; 8] = ["year", "month", "day", "hour", "minute", "second", "millisecond", "weekday"];
+const DURATION_UNITS: [(&str, f64); 6] = [
+    ("weeks", 7.0 * MILLIS_PER_DAY),
+    ("days", MILLIS_PER_DAY),
+    ("hours", MILLIS_PER_HOUR),
+    ("minutes", MILLIS_PER_MINUTE),
+    ("seconds", MILLIS_PER_SECOND),
+    ("milliseconds", 1.0),
+];
+
+/// Source of the current time for `Time::now`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Clock {
+    /// `js_sys::Date` in the browser, system time natively.
+    System,
+    /// Always the given milliseconds since the Unix epoch.
+    Fixed(f64),
+}
+
+impl Clock {
+    pub fn now(&self) -> f64 {
+        match self {
+            Clock::System => now_millis(),
+            Clock::Fixed(millis) => *millis,
+        }
+    }
+}
+
+/// Times are milliseconds since the Unix epoch, in UTC.
+pub fn module() -> Module {
+    Module::new("Time", "Timestamps in milliseconds since the Unix epoch, in UTC.")
+        .requires(Capabilities::Deterministic)
+        .function("now", "Time::now ~ ()", "Current time from the script's clock.", "Time::now ~ ()", now)
+        .function(
+            "parse",
+            "Time::parse ~ text",
+            "Time of ISO-8601 text, a date or date and time with an optional offset.",
+            "Time::parse ~ \"2024-03-05T10:20:30Z\"",
+            parse,
+        )
+        .function(
+            "format",
+            "Time::format ~ time",
+            "ISO-8601 text of a time, the same as JavaScript's toISOString.",
+            "Time::format ~ 0",
+            format,
+        )
+        .function(
+            "components",
+            "Time::components ~ time",
+            "Associations of :year, :month, :day, :hour, :minute, :second, :millisecond and :weekday.",
+            "(Time::components ~ 0).year",
+            components,
+        )
+        .function(
+            "day_of_week",
+            "Time::day_of_week ~ time",
+            "Day of the week from 1 for Monday to 7 for Sunday.",
+            "Time::day_of_week ~ 0",
+            day_of_week,
+        )
+        .function(
+            "duration",
+            "Time::duration ~ (:unit = amount...)",
+            "Milliseconds in amounts of weeks, days, hours, minutes, seconds and milliseconds.",
+            "Time::duration ~ (:days = 1, :hours = 2)",
+            duration,
+        )
+        .function(
+            "add",
+            "Time::add ~ (time, duration)",
+            "Time moved by milliseconds or unit amounts, years and months move by calendar months.",
+            "Time::format ~ (Time::add ~ (0, :months = 1))",
+            add,
+        )
+        .function(
+            "diff",
+            "Time::diff ~ (later, earlier)",
+            "Milliseconds from the earlier time to the later.",
+            "Time::diff ~ (86400000, 0)",
+            diff,
+        )
+}
+
+// days since 1970-01-01 for a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+// inverse of days_from_civil
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+
+    (if month <= 2 { year_of_era + era * 400 + 1 } else { year_of_era + era * 400 }, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    days_from_civil(year + month / 12, month % 12 + 1, 1) - days_from_civil(year, month, 1)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    millisecond: i64,
+}
+
+impl DateTime {
+    fn from_millis(millis: f64) -> Self {
+        let millis = millis.floor() as i64;
+        let days = millis.div_euclid(MILLIS_PER_DAY as i64);
+        let of_day = millis.rem_euclid(MILLIS_PER_DAY as i64);
+        let (year, month, day) = civil_from_days(days);
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: of_day / MILLIS_PER_HOUR as i64,
+            minute: of_day % MILLIS_PER_HOUR as i64 / MILLIS_PER_MINUTE as i64,
+            second: of_day % MILLIS_PER_MINUTE as i64 / MILLIS_PER_SECOND as i64,
+            millisecond: of_day % MILLIS_PER_SECOND as i64,
+        }
+    }
+
+    fn to_millis(self) -> f64 {
+        let days = days_from_civil(self.year, self.month, self.day);
+        days as f64 * MILLIS_PER_DAY
+            + self.hour as f64 * MILLIS_PER_HOUR
+            + self.minute as f64 * MILLIS_PER_MINUTE
+            + self.second as f64 * MILLIS_PER_SECOND
+            + self.millisecond as f64
+    }
+
+    // ISO-8601 numbering, Monday is 1 and Sunday is 7
+    fn weekday(self) -> i64 {
+        // 1970-01-01 was a Thursday
+        (days_from_civil(self.year, self.month, self.day) + 3).rem_euclid(7) + 1
+    }
+}
+
+// `2024-03-05`, `2024-03-05T10:20`, `2024-03-05T10:20:30.500Z` or with an offset like `+02:00`
+fn parse_iso(text: &str) -> Option<f64> {
+    let number = |from: &str, start: usize, len: usize| -> Option<i64> {
+        let part = from.get(start..start + len)?;
+        match part.chars().all(|c| c.is_ascii_digit()) {
+            true => part.parse().ok(),
+            false => None,
+        }
+    };
+    let expect = |from: &str, at: usize, c: char| from[at..].starts_with(c);
+
+    // positions below are byte offsets, only valid when every character is a single byte
+    if !text.is_ascii() || text.len() < 10 || !expect(text, 4, '-') || !expect(text, 7, '-') {
+        return None;
+    }
+
+    let mut time = DateTime {
+        year: number(text, 0, 4)?,
+        month: number(text, 5, 2)?,
+        day: number(text, 8, 2)?,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        millisecond: 0,
+    };
+
+    let mut rest = &text[10..];
+    if rest.starts_with('T') || rest.starts_with(' ') {
+        if rest.len() < 6 || !expect(rest, 3, ':') {
+            return None;
+        }
+        time.hour = number(rest, 1, 2)?;
+        time.minute = number(rest, 4, 2)?;
+        rest = &rest[6..];
+
+        if rest.starts_with(':') {
+            time.second = number(rest, 1, 2)?;
+            rest = &rest[3..];
+
+            if rest.starts_with('.') {
+                let digits = rest[1..].chars().take_while(|c| c.is_ascii_digit()).count();
+                // only milliseconds are kept
+                let fraction = format!("{:0<3}", &rest[1..1 + digits.min(3)]);
+                time.millisecond = fraction.parse().ok()?;
+                rest = &rest[1 + digits..];
+            }
+        }
+    }
+
+    let offset = match rest {
+        "" | "Z" => 0,
+        _ if rest.len() == 6 && (rest.starts_with('+') || rest.starts_with('-')) && expect(rest, 3, ':') => {
+            let minutes = number(rest, 1, 2)? * 60 + number(rest, 4, 2)?;
+            match rest.starts_with('-') {
+                true => -minutes,
+                false => minutes,
+            }
+        }
+        _ => return None,
+    };
+
+    let valid = (1..=12).contains(&time.month)
+        && time.day >= 1
+        && time.day <= days_in_month(time.year, time.month)
+        && time.hour < 24
+        && time.minute < 60
+        && time.second < 60;
+
+    match valid {
+        true => Some(time.to_millis() - offset as f64 * MILLIS_PER_MINUTE),
+        false => None,
+    }
+}
+
+// same as JavaScript's Date.toISOString
+fn format_iso(millis: f64) -> String {
+    let time = DateTime::from_millis(millis);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        time.year, time.month, time.day, time.hour, time.minute, time.second, time.millisecond
+    )
+}
+
+fn as_float(number: SimpleNumber) -> f64 {
+    match number {
+        SimpleNumber::Integer(i) => i as f64,
+        SimpleNumber::Float(f) => f,
+    }
+}
+
+fn time_of(addr: usize, data: &SimpleGarnishData) -> Option<f64> {
+    number_of(addr, data).map(as_float).filter(|t| t.is_finite())
+}
+
+// associations of unit symbols to amounts, `:days = 2, :hours = 3`
+// a single association doesn't need to be in a list
+fn duration_amounts(addr: usize, data: &SimpleGarnishData) -> Option<Vec<(u64, f64)>> {
+    arguments(addr, data)
+        .iter()
+        .map(|item| match data.get_data().get(*item)? {
+            SimpleData::Pair(left, right) => match data.get_data().get(*left)? {
+                SimpleData::Symbol(symbol) => Some((*symbol, as_float(number_of(*right, data)?))),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+// milliseconds for units of a fixed length, None for an unknown unit
+fn fixed_millis(amounts: &[(u64, f64)], skip: &[&str]) -> Option<f64> {
+    amounts
+        .iter()
+        .filter(|(symbol, _)| !skip.iter().any(|name| symbol_value(name) == *symbol))
+        .map(|(symbol, amount)| {
+            DURATION_UNITS
+                .iter()
+                .find(|(name, _)| symbol_value(name) == *symbol)
+                .map(|(_, millis)| amount * millis)
+        })
+        .sum()
+}
+
+fn add_time(value: Option<f64>, data: &mut SimpleGarnishData) -> NativeResult {
+    match value {
+        Some(value) => Ok(data.add_number(SimpleNumber::Float(value))?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+fn now(context: &mut BrowserContext, _: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let now = context.clock().now();
+    add_time(Some(now), data)
+}
+
+fn parse(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let time = text_of(input, data).and_then(|text| parse_iso(&text));
+    add_time(time, data)
+}
+
+fn format(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match time_of(input, data) {
+        Some(time) => Ok(add_text(&format_iso(time), data)?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+// :year, :month, :day, :hour, :minute, :second, :millisecond and :weekday associations
+fn components(context: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let time = match time_of(input, data) {
+        Some(time) => DateTime::from_millis(time),
+        None => return Ok(data.add_unit()?),
+    };
+
+    let values = [
+        time.year,
+        time.month,
+        time.day,
+        time.hour,
+        time.minute,
+        time.second,
+        time.millisecond,
+        time.weekday(),
+    ];
+
+    let mut pairs = vec![];
+    for (name, value) in COMPONENTS.iter().zip(values.iter()) {
+        context.add_symbol_name(name);
+        let symbol = data.add_symbol(symbol_value(name))?;
+        let number = data.add_number(SimpleNumber::Integer(*value as i32))?;
+        pairs.push(data.add_pair((symbol, number))?);
+    }
+
+    Ok(add_list(&pairs, data)?)
+}
+
+fn day_of_week(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    match time_of(input, data) {
+        Some(time) => Ok(data.add_number(SimpleNumber::Integer(DateTime::from_millis(time).weekday() as i32))?),
+        None => Ok(data.add_unit()?),
+    }
+}
+
+// milliseconds in a duration of weeks, days, hours, minutes, seconds and milliseconds
+fn duration(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let millis = duration_amounts(input, data).and_then(|amounts| fixed_millis(&amounts, &[]));
+    add_time(millis, data)
+}
+
+// (time, duration) where duration is milliseconds or associations of units
+// years and months move the date by calendar months, keeping the day within the new month
+fn add(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let (time, duration) = match arguments(input, data).as_slice() {
+        [time, duration] => match time_of(*time, data) {
+            Some(time) => (time, *duration),
+            None => return Ok(data.add_unit()?),
+        },
+        _ => return Ok(data.add_unit()?),
+    };
+
+    if let Some(millis) = number_of(duration, data) {
+        return add_time(Some(time + as_float(millis)), data);
+    }
+
+    let amounts = match duration_amounts(duration, data) {
+        Some(amounts) => amounts,
+        None => return Ok(data.add_unit()?),
+    };
+
+    let amount_of = |name: &str| -> f64 {
+        amounts
+            .iter()
+            .filter(|(symbol, _)| *symbol == symbol_value(name))
+            .map(|(_, amount)| amount)
+            .sum()
+    };
+
+    let months = (amount_of("years") * 12.0 + amount_of("months")).trunc() as i64;
+    let mut date = DateTime::from_millis(time);
+    if months != 0 {
+        let total = match (date.year * 12 + (date.month - 1)).checked_add(months) {
+            Some(total) if total.div_euclid(12).abs() <= MAX_YEAR => total,
+            _ => return Ok(data.add_unit()?),
+        };
+        date.year = total.div_euclid(12);
+        date.month = total.rem_euclid(12) + 1;
+        date.day = date.day.min(days_in_month(date.year, date.month));
+    }
+
+    let millis = fixed_millis(&amounts, &["years", "months"]);
+    // keep any fraction of a millisecond from the original time
+    add_time(millis.map(|millis| date.to_millis() + (time - time.floor()) + millis), data)
+}
+
+// milliseconds from the second time to the first
+fn diff(_: &mut BrowserContext, input: usize, data: &mut SimpleGarnishData) -> NativeResult {
+    let result = match arguments(input, data).as_slice() {
+        [later, earlier] => time_of(*later, data).zip(time_of(*earlier, data)).map(|(a, b)| a - b),
+        _ => None,
+    };
+
+    add_time(result, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::library::time::{civil_from_days, days_from_civil};
+    use crate::script::GarnishScript;
+
+    // 2024-03-05T10:20:30.500Z, a Tuesday
+    const FIXED: f64 = 1709634030500.0;
+
+    fn result_of(source: &str) -> Option<String> {
+        let mut script = GarnishScript::new("main".to_string(), source.to_string());
+        script.set_fixed_time(Some(FIXED));
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_error(), None);
+        script.get_execution_result(0)
+    }
+
+    #[test]
+    fn civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn fixed_clock() {
+        assert_eq!(result_of("Time::now ~ ()"), Some(FIXED.to_string()));
+        assert_eq!(result_of("Time::format ~ (Time::now ~ ())"), Some("\"2024-03-05T10:20:30.500Z\"".to_string()));
+    }
+
+    #[test]
+    fn system_clock() {
+        let mut script = GarnishScript::new("main".to_string(), "Time::now ~ ()".to_string());
+        script.compile();
+        script.execute();
+
+        let now: f64 = script.get_execution_result(0).unwrap().parse().unwrap();
+        assert!(now > FIXED);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(result_of("Time::parse ~ \"2024-03-05T10:20:30.500Z\""), Some(FIXED.to_string()));
+        assert_eq!(result_of("Time::parse ~ \"2024-03-05T12:20:30.5+02:00\""), Some(FIXED.to_string()));
+        assert_eq!(result_of("Time::parse ~ \"1970-01-02\""), Some("86400000".to_string()));
+        assert_eq!(result_of("Time::parse ~ \"1970-01-01T00:01\""), Some("60000".to_string()));
+        assert_eq!(result_of("Time::parse ~ \"2023-02-29\""), Some("()".to_string()));
+        assert_eq!(result_of("Time::parse ~ \"not a date\""), Some("()".to_string()));
+        assert_eq!(result_of("Time::parse ~ \"abcé123456\""), Some("()".to_string()));
+        assert_eq!(result_of("Time::parse ~ \"2024-03-05Té0:00\""), Some("()".to_string()));
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(
+            result_of("Time::format ~ (Time::parse ~ \"1969-07-20T20:17:40.000Z\")"),
+            Some("\"1969-07-20T20:17:40.000Z\"".to_string())
+        );
+    }
+
+    #[test]
+    fn components() {
+        assert_eq!(
+            result_of("Time::components ~ (Time::now ~ ())"),
+            Some(":year = 2024, :month = 3, :day = 5, :hour = 10, :minute = 20, :second = 30, :millisecond = 500, :weekday = 2".to_string())
+        );
+        assert_eq!(result_of("(Time::components ~ (Time::now ~ ())).month"), Some("3".to_string()));
+    }
+
+    #[test]
+    fn day_of_week() {
+        assert_eq!(result_of("Time::day_of_week ~ (Time::now ~ ())"), Some("2".to_string()));
+        assert_eq!(result_of("Time::day_of_week ~ (Time::parse ~ \"2024-03-10\")"), Some("7".to_string()));
+        assert_eq!(result_of("Time::day_of_week ~ 0"), Some("4".to_string()));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(result_of("Time::duration ~ (:days = 1, :hours = 2)"), Some("93600000".to_string()));
+        assert_eq!(result_of("Time::duration ~ :seconds = 1.5"), Some("1500".to_string()));
+        assert_eq!(result_of("Time::duration ~ :fortnights = 1"), Some("()".to_string()));
+        assert_eq!(
+            result_of("Time::format ~ (Time::add ~ (Time::now ~ (), :days = 30))"),
+            Some("\"2024-04-04T10:20:30.500Z\"".to_string())
+        );
+        assert_eq!(
+            result_of("Time::format ~ (Time::add ~ (Time::now ~ (), Time::duration ~ :hours = 14))"),
+            Some("\"2024-03-06T00:20:30.500Z\"".to_string())
+        );
+        assert_eq!(
+            result_of("Time::diff ~ (Time::parse ~ \"2024-01-02\", Time::parse ~ \"2024-01-01\")"),
+            Some("86400000".to_string())
+        );
+    }
+
+    #[test]
+    fn calendar_months() {
+        assert_eq!(
+            result_of("Time::format ~ (Time::add ~ (Time::parse ~ \"2024-01-31\", :months = 1))"),
+            Some("\"2024-02-29T00:00:00.000Z\"".to_string())
+        );
+        assert_eq!(
+            result_of("Time::format ~ (Time::add ~ (Time::parse ~ \"2024-02-29\", (:years = 1, :days = 1)))"),
+            Some("\"2025-03-01T00:00:00.000Z\"".to_string())
+        );
+        assert_eq!(
+            result_of("Time::format ~ (Time::add ~ (Time::parse ~ \"2024-01-15\", :months = 0 - 2))"),
+            Some("\"2023-11-15T00:00:00.000Z\"".to_string())
+        );
+    }
+
+    #[test]
+    fn calendar_months_out_of_range() {
+        assert_eq!(result_of("Time::add ~ (0, :years = 1e300)"), Some("()".to_string()));
+        assert_eq!(result_of("Time::add ~ (0, :years = 0 - 1e300)"), Some("()".to_string()));
+        assert_eq!(result_of("Time::add ~ (0, :months = 1000000000000000.0)"), Some("()".to_string()));
+        assert_eq!(result_of("Time::add ~ (0, :years = 300000)"), Some("()".to_string()));
+    }
+}
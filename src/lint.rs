@@ -0,0 +1,364 @@
+use crate::annotations::AnnotationRegistry;
+use crate::context::BrowserContext;
+use crate::navigation::{definition_sites, include_location, references, Location};
+use crate::script::SourceDetails;
+use crate::tokens::{end_position, is_resolved_identifier};
+use garnish_lang::compiler::lex::{Lexer, LexerToken, TokenType};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    location: Location,
+}
+
+#[wasm_bindgen]
+impl Diagnostic {
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_location(&self) -> Location {
+        self.location.clone()
+    }
+}
+
+impl Diagnostic {
+    fn error(message: String, location: Location) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            location,
+        }
+    }
+
+    fn warning(message: String, location: Location) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message,
+            location,
+        }
+    }
+}
+
+/// Errors and warnings for a compiled set of sources.
+///
+/// Reports identifiers the context can't resolve, `@Def`s and includes that are never referenced
+/// and `@Def`s with the same name as a built-in constant.
+/// Calls passing a literal list of the wrong length to a `@Def` with parameters are errors,
+/// as are built-ins the context's capabilities don't allow.
+pub fn lint(
+    context: &BrowserContext,
+    source: &SourceDetails,
+    includes: &[SourceDetails],
+    registry: &AnnotationRegistry,
+) -> Vec<Diagnostic> {
+    let sources: Vec<&SourceDetails> = std::iter::once(source).chain(includes).collect();
+
+    let expressions: HashSet<&String> = context.expression_names().collect();
+    let constants: HashSet<&String> = context.constants().map(|(name, _)| name).collect();
+    let natives: HashSet<&String> = context.native_names().collect();
+
+    let mut diagnostics = vec![];
+    let mut referenced = HashSet::new();
+
+    for source in &sources {
+        for reference in references(source.text(), registry) {
+            if reference.is_definition() {
+                continue;
+            }
+
+            let token = reference.token();
+            let name = token.get_text();
+            if !expressions.contains(name) && !constants.contains(name) && !natives.contains(name) {
+                let location = Location::new(
+                    source.name(),
+                    (token.get_line(), token.get_column()),
+                    end_position(token),
+                );
+
+                diagnostics.push(match context.disallowed(name) {
+                    Some(message) => Diagnostic::error(message, location),
+                    None => Diagnostic::warning(format!("Unresolved identifier `{}`", name), location),
+                });
+            }
+
+            referenced.insert(name.clone());
+        }
+    }
+
+    for source in &sources {
+        for site in definition_sites(source.name(), source.text(), registry) {
+            if constants.contains(site.name()) {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Definition `{}` shadows a built-in constant", site.name()),
+                    site.location().clone(),
+                ));
+            }
+
+            if !referenced.contains(site.name()) {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Definition `{}` is never referenced", site.name()),
+                    site.location().clone(),
+                ));
+            }
+        }
+    }
+
+    let arities: HashMap<String, usize> = sources
+        .iter()
+        .flat_map(|source| definition_sites(source.name(), source.text(), registry))
+        .filter(|site| site.parameters().len() > 1)
+        .map(|site| (site.name().clone(), site.parameters().len()))
+        .collect();
+
+    for source in &sources {
+        diagnostics.extend(arity_errors(source, &arities));
+    }
+
+    for include in includes {
+        // using any of an include's definitions counts as using the include
+        let used = referenced.contains(include.name())
+            || definition_sites(include.name(), include.text(), registry)
+                .iter()
+                .any(|site| referenced.contains(site.name()));
+
+        if !used {
+            diagnostics.push(Diagnostic::warning(
+                format!("Include `{}` is never referenced", include.name()),
+                include_location(include),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+// calls like `add ~ (1, 2, 3)`, where the number of items is known from the source
+fn arity_errors(source: &SourceDetails, arities: &HashMap<String, usize>) -> Vec<Diagnostic> {
+    let tokens: Vec<LexerToken> = Lexer::new(source.text())
+        .filter(|t| {
+            !matches!(
+                t.get_token_type(),
+                TokenType::Whitespace | TokenType::Subexpression | TokenType::LineAnnotation
+            )
+        })
+        .collect();
+
+    let mut diagnostics = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        let expected = match arities.get(token.get_text()) {
+            Some(expected) if is_resolved_identifier(&tokens, i) => *expected,
+            _ => continue,
+        };
+
+        match (tokens.get(i + 1), tokens.get(i + 2)) {
+            (Some(apply), Some(group))
+                if apply.get_token_type() == TokenType::Apply
+                    && group.get_token_type() == TokenType::StartGroup => {}
+            _ => continue,
+        }
+
+        if let Some((given, end)) = literal_list_length(&tokens[(i + 2)..]) {
+            if given != expected {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "`{}` takes {} parameters but {} were given",
+                        token.get_text(),
+                        expected,
+                        given
+                    ),
+                    Location::new(source.name(), (token.get_line(), token.get_column()), end_position(end)),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+// items in a group starting at the first token, along with its closing token
+// groups without a comma aren't lists
+fn literal_list_length(tokens: &[LexerToken]) -> Option<(usize, &LexerToken)> {
+    let mut depth = 0;
+    let mut commas = 0;
+
+    for token in tokens {
+        match token.get_token_type() {
+            TokenType::StartGroup | TokenType::StartExpression | TokenType::StartSideEffect => depth += 1,
+            TokenType::EndGroup | TokenType::EndExpression | TokenType::EndSideEffect => {
+                depth -= 1;
+                if depth == 0 {
+                    return match commas {
+                        0 => None,
+                        _ => Some((commas + 1, token)),
+                    };
+                }
+            }
+            TokenType::Comma if depth == 1 => commas += 1,
+            _ => (),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotations::AnnotationHandler;
+    use crate::lint::Severity;
+    use crate::script::GarnishScript;
+    use garnish_lang_annotations_collector::{PartBehavior, PartParser};
+
+    fn messages(script: &GarnishScript) -> Vec<String> {
+        script
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.get_message())
+            .collect()
+    }
+
+    #[test]
+    fn clean_script() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def add_5 { $ + 5 }\n\n(value = 10).value ~ add_5 + Math::PI + (Str::length ~ \"abc\")".to_string(),
+        );
+        script.compile();
+
+        assert!(script.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn unresolved_identifier() {
+        let mut script = GarnishScript::new("main".to_string(), "5 + missing".to_string());
+        script.compile();
+
+        let diagnostics = script.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].get_severity(), Severity::Warning);
+        assert_eq!(diagnostics[0].get_message(), "Unresolved identifier `missing`");
+        assert_eq!(diagnostics[0].get_location().start(), (0, 4));
+        assert_eq!(diagnostics[0].get_location().end(), (0, 11));
+    }
+
+    #[test]
+    fn unused_definition() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def unused { 5 }\n\n10".to_string());
+        script.compile();
+
+        let diagnostics = script.get_diagnostics();
+        assert_eq!(messages(&script), vec!["Definition `unused` is never referenced"]);
+        assert_eq!(diagnostics[0].get_location().end(), (0, 17));
+    }
+
+    #[test]
+    fn unused_include() {
+        let mut script = GarnishScript::new("main".to_string(), "half ~ 10".to_string());
+        script.include("helpers".to_string(), "@Def half { $ / 2 }\n\n5".to_string());
+        script.include("unused".to_string(), "5".to_string());
+        script.compile();
+
+        assert_eq!(messages(&script), vec!["Include `unused` is never referenced"]);
+        assert_eq!(script.get_diagnostics()[0].get_location().get_source(), "unused");
+    }
+
+    #[test]
+    fn shadowed_constant() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def Math::PI { 3 }\n\nMath::PI".to_string(),
+        );
+        script.compile();
+
+        assert_eq!(messages(&script), vec!["Definition `Math::PI` shadows a built-in constant"]);
+    }
+
+    #[test]
+    fn test_names_not_references() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def add_5 { $ + 5 }\n\n@Test adds_five { (add_5 ~ 5) == 10 }".to_string(),
+        );
+        script.compile();
+
+        assert!(script.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn cleared_on_compile_error() {
+        let mut script = GarnishScript::new("main".to_string(), "missing".to_string());
+        script.compile();
+        assert_eq!(script.get_diagnostics().len(), 1);
+
+        script.set_text("(missing".to_string());
+        script.compile();
+        assert!(script.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn removed_definition_unresolved() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def old_name { 5 }\n\n5 + old_name".to_string());
+        script.compile();
+        assert!(script.get_diagnostics().is_empty());
+
+        script.set_text("5 + old_name".to_string());
+        script.compile();
+        assert_eq!(messages(&script), vec!["Unresolved identifier `old_name`"]);
+    }
+
+    #[test]
+    fn registered_annotations_not_references() {
+        let mut script = GarnishScript::new("main".to_string(), "@Owner alice\n\n5".to_string());
+        script.register_annotation(
+            AnnotationHandler::new("@Owner", |_, _| Ok(())).part(PartParser::new(PartBehavior::TokenCount(1))),
+        )
+        .unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_execution_result(0), Some("5".to_string()));
+        assert!(script.get_diagnostics().is_empty());
+        assert_eq!(script.outline("@Owner alice\n\n5".to_string()).len(), 1);
+    }
+
+    #[test]
+    fn parameters_not_unresolved() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def add(a, b) { a + b }\n\nadd ~ (1, 2)".to_string(),
+        );
+        script.compile();
+
+        assert!(script.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn wrong_arity() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def add(a, b) { a + b }\n\nadd ~ (1, 2, 3)\n\nadd ~ ((1, 2), 3)\n\nadd ~ (5)".to_string(),
+        );
+        script.compile();
+
+        let diagnostics = script.get_diagnostics();
+        assert_eq!(messages(&script), vec!["`add` takes 2 parameters but 3 were given"]);
+        assert_eq!(diagnostics[0].get_severity(), Severity::Error);
+        assert_eq!(diagnostics[0].get_location().start(), (2, 0));
+        assert_eq!(diagnostics[0].get_location().end(), (2, 15));
+    }
+}
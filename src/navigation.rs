@@ -0,0 +1,468 @@
+use crate::compile::{
+    collect_annotations, definition_documentation, definition_name, definition_parameters,
+    CONST_ANNOTATION, DEF_ANNOTATION, TEST_ANNOTATION,
+};
+use crate::annotations::AnnotationRegistry;
+use crate::completion::CompletionKind;
+use crate::context::BrowserContext;
+use crate::display::{format_constant, FormatOptions};
+use crate::script::SourceDetails;
+use crate::tokens::{end_position, identifier_at, is_resolved_identifier, offset_of};
+use garnish_lang::compiler::lex::{Lexer, LexerToken, TokenType};
+use garnish_lang_annotations_collector::TokenBlock;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Range of text within a named source. Lines and columns are zero based, end is exclusive.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    source: String,
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+#[wasm_bindgen]
+impl Location {
+    pub fn get_source(&self) -> String {
+        self.source.clone()
+    }
+
+    pub fn get_start_line(&self) -> usize {
+        self.start_line
+    }
+
+    pub fn get_start_column(&self) -> usize {
+        self.start_column
+    }
+
+    pub fn get_end_line(&self) -> usize {
+        self.end_line
+    }
+
+    pub fn get_end_column(&self) -> usize {
+        self.end_column
+    }
+}
+
+impl Location {
+    pub fn new(source: &str, start: (usize, usize), end: (usize, usize)) -> Self {
+        Location {
+            source: source.to_string(),
+            start_line: start.0,
+            start_column: start.1,
+            end_line: end.0,
+            end_column: end.1,
+        }
+    }
+
+    pub fn start(&self) -> (usize, usize) {
+        (self.start_line, self.start_column)
+    }
+
+    pub fn end(&self) -> (usize, usize) {
+        (self.end_line, self.end_column)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hover {
+    name: String,
+    kind: CompletionKind,
+    contents: String,
+    documentation: Option<String>,
+    location: Option<Location>,
+}
+
+#[wasm_bindgen]
+impl Hover {
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_kind(&self) -> CompletionKind {
+        self.kind
+    }
+
+    /// Source text of the definition or include, the formatted value of a constant
+    /// or the signature of a built-in function.
+    pub fn get_contents(&self) -> String {
+        self.contents.clone()
+    }
+
+    /// Text of the `@Doc` annotation preceding a definition, or the description of a built-in.
+    pub fn get_documentation(&self) -> Option<String> {
+        self.documentation.clone()
+    }
+
+    pub fn get_location(&self) -> Option<Location> {
+        self.location.clone()
+    }
+}
+
+/// A `@Def` block found in source text.
+pub struct DefinitionSite {
+    name: String,
+    location: Location,
+    text: String,
+    documentation: Option<String>,
+    parameters: Vec<String>,
+}
+
+impl DefinitionSite {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+
+    pub fn documentation(&self) -> Option<&String> {
+        self.documentation.as_ref()
+    }
+
+    /// Names from the parameter list, empty when the definition only uses `$`.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+}
+
+/// Identifier that refers to a globally named expression or constant.
+pub struct Reference {
+    token: LexerToken,
+    is_definition: bool,
+}
+
+impl Reference {
+    pub fn token(&self) -> &LexerToken {
+        &self.token
+    }
+
+    /// True for the name of a `@Def` or `@Const` block, rather than a use of it.
+    pub fn is_definition(&self) -> bool {
+        self.is_definition
+    }
+}
+
+/// All top level `@Def` blocks in a source.
+pub fn definition_sites(source_name: &str, text: &str, registry: &AnnotationRegistry) -> Vec<DefinitionSite> {
+    let tokens: Vec<LexerToken> = Lexer::new(text).collect();
+    let blocks = match collect_annotations(&tokens, registry) {
+        Ok(blocks) => blocks,
+        Err(_) => return vec![],
+    };
+    let documentation = definition_documentation(&blocks);
+
+    blocks
+        .iter()
+        .filter(|block| block.annotation_text() == DEF_ANNOTATION)
+        .filter_map(|block| {
+            let name = definition_name(block).ok()?;
+            let (start, end) = block_range(block, &tokens)?;
+            Some(DefinitionSite {
+                name: name.get_text().clone(),
+                location: Location::new(source_name, start, end),
+                text: text[offset_of(text, start.0, start.1)..offset_of(text, end.0, end.1)]
+                    .to_string(),
+                documentation: documentation.get(name.get_text()).cloned(),
+                parameters: definition_parameters(block)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|t| t.get_text().clone())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Range of an annotation block, from its annotation token to the end of its last part.
+// collected blocks don't include the annotation token itself
+// find the annotation token directly preceding the first part
+pub fn block_range(block: &TokenBlock, tokens: &[LexerToken]) -> Option<((usize, usize), (usize, usize))> {
+    let first = block.parts().iter().find_map(|part| part.first())?;
+    let last = block.parts().iter().rev().find_map(|part| part.last())?;
+    let first_position = (first.get_line(), first.get_column());
+
+    let annotation = tokens
+        .iter()
+        .rev()
+        .filter(|t| t.get_token_type() == TokenType::Annotation)
+        .find(|t| (t.get_line(), t.get_column()) < first_position)?;
+
+    Some((
+        (annotation.get_line(), annotation.get_column()),
+        end_position(last),
+    ))
+}
+
+/// Identifiers in text that are resolved by name, including `@Def` names.
+/// Access names after a period and pair keys are symbols and aren't included,
+/// neither are tokens of registered annotations, which are up to their handler.
+pub fn references(text: &str, registry: &AnnotationRegistry) -> Vec<Reference> {
+    let tokens: Vec<LexerToken> = Lexer::new(text).collect();
+
+    let blocks = collect_annotations(&tokens, registry).unwrap_or_default();
+    let names_of = |annotation: &str| -> HashSet<(usize, usize)> {
+        blocks
+            .iter()
+            .filter(|block| block.annotation_text() == annotation)
+            .filter_map(|block| definition_name(block).ok())
+            .map(|t| (t.get_line(), t.get_column()))
+            .collect()
+    };
+
+    let definition_names: HashSet<(usize, usize)> = names_of(DEF_ANNOTATION)
+        .union(&names_of(CONST_ANNOTATION))
+        .cloned()
+        .collect();
+    // test names aren't resolvable, so aren't references at all
+    let test_names = names_of(TEST_ANNOTATION);
+
+    let registered_tokens: HashSet<(usize, usize)> = blocks
+        .iter()
+        .filter(|block| registry.get(block.annotation_text()).is_some())
+        .flat_map(|block| block.parts().iter().flatten())
+        .map(|t| (t.get_line(), t.get_column()))
+        .collect();
+
+    // parameters are local to their definition
+    let parameter_names: HashSet<(usize, usize)> = blocks
+        .iter()
+        .filter(|block| block.annotation_text() == DEF_ANNOTATION)
+        .flat_map(|block| {
+            let parameters: Vec<&String> = definition_parameters(block)
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.get_text())
+                .collect();
+
+            block
+                .parts()
+                .get(1)
+                .into_iter()
+                .flatten()
+                .filter(move |t| parameters.contains(&t.get_text()))
+                .map(|t| (t.get_line(), t.get_column()))
+        })
+        .collect();
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| is_resolved_identifier(&tokens, *i))
+        .map(|(_, t)| t)
+        .filter(|t| {
+            let position = (t.get_line(), t.get_column());
+            !test_names.contains(&position)
+                && !parameter_names.contains(&position)
+                && !registered_tokens.contains(&position)
+        })
+        .map(|t| Reference {
+            token: t.clone(),
+            is_definition: definition_names.contains(&(t.get_line(), t.get_column())),
+        })
+        .collect()
+}
+
+/// Text is treated as the current contents of `source`, includes are searched as compiled.
+#[allow(clippy::too_many_arguments)]
+pub fn hover(
+    text: &str,
+    line: usize,
+    column: usize,
+    context: &BrowserContext,
+    source: &SourceDetails,
+    includes: &[SourceDetails],
+    registry: &AnnotationRegistry,
+    options: &FormatOptions,
+) -> Option<Hover> {
+    let name = identifier_name(text, line, column)?;
+
+    if let Some(site) = find_definition(&name, text, source, includes, registry) {
+        return Some(Hover {
+            name,
+            kind: CompletionKind::Definition,
+            contents: site.text,
+            documentation: site.documentation,
+            location: Some(site.location),
+        });
+    }
+
+    if let Some(include) = includes.iter().find(|include| include.name() == &name) {
+        return Some(Hover {
+            name,
+            kind: CompletionKind::Include,
+            contents: include.text().clone(),
+            documentation: None,
+            location: Some(include_location(include)),
+        });
+    }
+
+    let documentation = context.member(&name).map(|member| member.get_description());
+
+    if let Some((_, value)) = context.constants().find(|(constant, _)| *constant == &name) {
+        return Some(Hover {
+            kind: CompletionKind::Constant,
+            contents: format_constant(value, options),
+            documentation,
+            location: None,
+            name,
+        });
+    }
+
+    context.member(&name).map(|member| Hover {
+        kind: CompletionKind::Function,
+        contents: member.get_signature(),
+        documentation,
+        location: None,
+        name,
+    })
+}
+
+/// Location of the `@Def` block or include the identifier at the given position refers to.
+pub fn definition(
+    text: &str,
+    line: usize,
+    column: usize,
+    source: &SourceDetails,
+    includes: &[SourceDetails],
+    registry: &AnnotationRegistry,
+) -> Option<Location> {
+    let name = identifier_name(text, line, column)?;
+
+    find_definition(&name, text, source, includes, registry)
+        .map(|site| site.location)
+        .or_else(|| {
+            includes
+                .iter()
+                .find(|include| include.name() == &name)
+                .map(include_location)
+        })
+}
+
+fn identifier_name(text: &str, line: usize, column: usize) -> Option<String> {
+    let tokens: Vec<LexerToken> = Lexer::new(text).collect();
+    identifier_at(&tokens, line, column).map(|t| t.get_text().clone())
+}
+
+fn find_definition(
+    name: &str,
+    text: &str,
+    source: &SourceDetails,
+    includes: &[SourceDetails],
+    registry: &AnnotationRegistry,
+) -> Option<DefinitionSite> {
+    std::iter::once((source.name().as_str(), text))
+        .chain(includes.iter().map(|i| (i.name().as_str(), i.text().as_str())))
+        .flat_map(|(source_name, text)| definition_sites(source_name, text, registry))
+        .find(|site| site.name() == name)
+}
+
+/// Range covering the whole text of an include.
+pub fn include_location(include: &SourceDetails) -> Location {
+    let tokens: Vec<LexerToken> = Lexer::new(include.text()).collect();
+    let end = tokens.last().map(end_position).unwrap_or((0, 0));
+    Location::new(include.name(), (0, 0), end)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::CompletionKind;
+    use crate::script::GarnishScript;
+
+    #[test]
+    fn hover_definition_in_text() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let text = "@Def add_5 { $ + 5 }\n\nadd_5 ~ 5".to_string();
+        let hover = script.hover(text, 2, 2).unwrap();
+
+        assert_eq!(hover.get_name(), "add_5");
+        assert_eq!(hover.get_kind(), CompletionKind::Definition);
+        assert_eq!(hover.get_contents(), "@Def add_5 { $ + 5 }");
+
+        let location = hover.get_location().unwrap();
+        assert_eq!(location.get_source(), "main");
+        assert_eq!(location.start(), (0, 0));
+        assert_eq!(location.end(), (0, 20));
+    }
+
+    #[test]
+    fn hover_definition_in_include() {
+        let mut script = GarnishScript::new("main".to_string(), "".to_string());
+        script.include("helpers".to_string(), "5\n\n@Def half {\n  $ / 2\n}".to_string());
+
+        let hover = script.hover("half ~ 10".to_string(), 0, 0).unwrap();
+        let location = hover.get_location().unwrap();
+
+        assert_eq!(hover.get_contents(), "@Def half {\n  $ / 2\n}");
+        assert_eq!(location.get_source(), "helpers");
+        assert_eq!(location.start(), (2, 0));
+        assert_eq!(location.end(), (4, 1));
+    }
+
+    #[test]
+    fn hover_include() {
+        let mut script = GarnishScript::new("main".to_string(), "".to_string());
+        script.include("add_5".to_string(), "$ + 5".to_string());
+
+        let hover = script.hover("add_5 ~ 10".to_string(), 0, 3).unwrap();
+
+        assert_eq!(hover.get_kind(), CompletionKind::Include);
+        assert_eq!(hover.get_contents(), "$ + 5");
+        assert_eq!(hover.get_location().unwrap().end(), (0, 5));
+    }
+
+    #[test]
+    fn hover_constant() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let hover = script.hover("5 + Math::IntegerMax".to_string(), 0, 6).unwrap();
+
+        assert_eq!(hover.get_kind(), CompletionKind::Constant);
+        assert_eq!(hover.get_contents(), i32::MAX.to_string());
+        assert!(hover.get_location().is_none());
+    }
+
+    #[test]
+    fn hover_nothing() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+
+        assert!(script.hover("5 + unknown".to_string(), 0, 6).is_none());
+        assert!(script.hover("5 + 5".to_string(), 0, 0).is_none());
+    }
+
+    #[test]
+    fn definition_location() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let text = "10\n\n@Def add_5 { $ + 5 }\n\nadd_5 ~ $".to_string();
+        let location = script.definition(text, 4, 0).unwrap();
+
+        assert_eq!(location.get_source(), "main");
+        assert_eq!(location.start(), (2, 0));
+        assert_eq!(location.end(), (2, 20));
+    }
+
+    #[test]
+    fn hover_native_function() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+        let hover = script.hover("Str::length ~ \"abc\"".to_string(), 0, 3).unwrap();
+
+        assert_eq!(hover.get_kind(), CompletionKind::Function);
+        assert_eq!(hover.get_contents(), "Str::length ~ text");
+        assert_eq!(hover.get_documentation(), Some("Number of characters.".to_string()));
+    }
+
+    #[test]
+    fn definition_of_constant() {
+        let script = GarnishScript::new("main".to_string(), "".to_string());
+
+        assert!(script.definition("Math::PI".to_string(), 0, 0).is_none());
+    }
+}
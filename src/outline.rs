@@ -0,0 +1,271 @@
+use crate::annotations::AnnotationRegistry;
+use crate::compile::{collect_annotations, definition_expression, definition_name, DEF_ANNOTATION};
+use crate::navigation::{block_range, Location};
+use crate::script::SourceDetails;
+use crate::tokens::{end_position, offset_of};
+use garnish_lang::compiler::lex::{Lexer, LexerToken, TokenType};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const MAX_SUMMARY_LENGTH: usize = 40;
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutlineKind {
+    Root,
+    Definition,
+    Expression,
+}
+
+/// Node in a source's outline. Ranges can be used as folding ranges.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineNode {
+    name: String,
+    kind: OutlineKind,
+    location: Location,
+    children: Vec<OutlineNode>,
+}
+
+#[wasm_bindgen]
+impl OutlineNode {
+    /// Definition name, or the start of the expression's text for other nodes.
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_kind(&self) -> OutlineKind {
+        self.kind
+    }
+
+    pub fn get_location(&self) -> Location {
+        self.location.clone()
+    }
+
+    pub fn get_children(&self) -> Vec<OutlineNode> {
+        self.children.clone()
+    }
+}
+
+/// Structure of a source in the order it appears.
+///
+/// Top level nodes are root subexpressions and `@Def` blocks,
+/// each containing the expression blocks nested inside them.
+/// Use `GarnishScript::outline` for sources with registered annotations.
+#[wasm_bindgen]
+pub fn outline(source: &SourceDetails) -> Vec<OutlineNode> {
+    annotated_outline(source, &AnnotationRegistry::new())
+}
+
+/// Outline of a source, leaving out blocks of the registry's annotations.
+pub fn annotated_outline(source: &SourceDetails, registry: &AnnotationRegistry) -> Vec<OutlineNode> {
+    let text = source.text();
+    let tokens: Vec<LexerToken> = Lexer::new(text).collect();
+    let blocks = match collect_annotations(&tokens, registry) {
+        Ok(blocks) => blocks,
+        Err(_) => return vec![],
+    };
+
+    let mut nodes = vec![];
+    for block in &blocks {
+        if block.annotation_text().is_empty() {
+            for expression in subexpressions(block.tokens()) {
+                let location = token_range(source.name(), expression);
+                nodes.push(OutlineNode {
+                    name: summary(text, &location),
+                    kind: OutlineKind::Root,
+                    children: expression_blocks(source.name(), text, expression),
+                    location,
+                });
+            }
+        } else if block.annotation_text() == DEF_ANNOTATION {
+            let (name, expression) = match (definition_name(block), definition_expression(block)) {
+                (Ok(name), Ok(expression)) => (name, expression),
+                _ => continue,
+            };
+
+            if let Some((start, end)) = block_range(block, &tokens) {
+                nodes.push(OutlineNode {
+                    name: name.get_text().clone(),
+                    kind: OutlineKind::Definition,
+                    location: Location::new(source.name(), start, end),
+                    children: expression_blocks(source.name(), text, expression),
+                });
+            }
+        }
+    }
+
+    nodes
+}
+
+// split on subexpression tokens not nested in groups or expressions
+// and trim whitespace and comments from each
+fn subexpressions(tokens: &[LexerToken]) -> Vec<&[LexerToken]> {
+    let mut expressions = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.get_token_type() {
+            TokenType::StartGroup | TokenType::StartExpression | TokenType::StartSideEffect => {
+                depth += 1
+            }
+            TokenType::EndGroup | TokenType::EndExpression | TokenType::EndSideEffect => {
+                depth -= 1
+            }
+            TokenType::Subexpression if depth == 0 => {
+                expressions.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    expressions.push(&tokens[start..]);
+
+    expressions
+        .into_iter()
+        .filter_map(|expression| {
+            let first = expression.iter().position(is_significant)?;
+            let last = expression.iter().rposition(is_significant)?;
+            Some(&expression[first..=last])
+        })
+        .collect()
+}
+
+fn expression_blocks(source_name: &str, text: &str, tokens: &[LexerToken]) -> Vec<OutlineNode> {
+    let mut stack: Vec<(&LexerToken, Vec<OutlineNode>)> = vec![];
+    let mut nodes = vec![];
+
+    for token in tokens {
+        match token.get_token_type() {
+            TokenType::StartExpression => stack.push((token, vec![])),
+            TokenType::EndExpression => {
+                let (start, children) = match stack.pop() {
+                    Some(open) => open,
+                    None => continue,
+                };
+
+                let location = Location::new(
+                    source_name,
+                    (start.get_line(), start.get_column()),
+                    end_position(token),
+                );
+                let node = OutlineNode {
+                    name: summary(text, &location),
+                    kind: OutlineKind::Expression,
+                    location,
+                    children,
+                };
+
+                match stack.last_mut() {
+                    Some((_, siblings)) => siblings.push(node),
+                    None => nodes.push(node),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    nodes
+}
+
+fn is_significant(token: &LexerToken) -> bool {
+    !matches!(
+        token.get_token_type(),
+        TokenType::Whitespace | TokenType::Subexpression | TokenType::LineAnnotation
+    )
+}
+
+fn token_range(source_name: &str, tokens: &[LexerToken]) -> Location {
+    let start = tokens
+        .first()
+        .map(|t| (t.get_line(), t.get_column()))
+        .unwrap_or((0, 0));
+    let end = tokens.last().map(end_position).unwrap_or(start);
+
+    Location::new(source_name, start, end)
+}
+
+// first line of a range's text, shortened
+fn summary(text: &str, location: &Location) -> String {
+    let (start, end) = (location.start(), location.end());
+    let range = &text[offset_of(text, start.0, start.1)..offset_of(text, end.0, end.1)];
+    let line = range.lines().next().unwrap_or("").trim();
+
+    match line.char_indices().nth(MAX_SUMMARY_LENGTH) {
+        Some((offset, _)) => format!("{}...", &line[..offset]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::outline::{outline, OutlineKind, OutlineNode};
+    use crate::script::SourceDetails;
+
+    fn outline_of(text: &str) -> Vec<OutlineNode> {
+        outline(&SourceDetails::new("main".to_string(), text.to_string()))
+    }
+
+    #[test]
+    fn root_expressions() {
+        let nodes = outline_of("@@ first\n5 + 5\n\n\"hello\"\n");
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].get_kind(), OutlineKind::Root);
+        assert_eq!(nodes[0].get_name(), "5 + 5");
+        assert_eq!(nodes[0].get_location().start(), (1, 0));
+        assert_eq!(nodes[0].get_location().end(), (1, 5));
+        assert_eq!(nodes[1].get_name(), "\"hello\"");
+        assert_eq!(nodes[1].get_location().start(), (3, 0));
+    }
+
+    #[test]
+    fn definitions_in_order() {
+        let nodes = outline_of("10\n\n@Def add_5 {\n  $ + 5\n}\n\nadd_5 ~ $");
+        let kinds: Vec<(String, OutlineKind)> = nodes
+            .iter()
+            .map(|n| (n.get_name(), n.get_kind()))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("10".to_string(), OutlineKind::Root),
+                ("add_5".to_string(), OutlineKind::Definition),
+                ("add_5 ~ $".to_string(), OutlineKind::Root),
+            ]
+        );
+
+        let location = nodes[1].get_location();
+        assert_eq!(location.get_source(), "main");
+        assert_eq!(location.start(), (2, 0));
+        assert_eq!(location.end(), (4, 1));
+    }
+
+    #[test]
+    fn nested_expressions() {
+        let nodes = outline_of("@Def outer {\n  { $ + { 5 } }\n}\n\n{ 10 } ~~");
+
+        let def_children = nodes[0].get_children();
+        assert_eq!(def_children.len(), 1);
+        assert_eq!(def_children[0].get_kind(), OutlineKind::Expression);
+        assert_eq!(def_children[0].get_name(), "{ $ + { 5 } }");
+        assert_eq!(def_children[0].get_location().start(), (1, 2));
+        assert_eq!(def_children[0].get_location().end(), (1, 15));
+
+        let inner = def_children[0].get_children();
+        assert_eq!(inner.len(), 1);
+        assert_eq!(inner[0].get_name(), "{ 5 }");
+
+        let root_children = nodes[1].get_children();
+        assert_eq!(root_children.len(), 1);
+        assert_eq!(root_children[0].get_name(), "{ 10 }");
+    }
+
+    #[test]
+    fn long_names_shortened() {
+        let nodes = outline_of(&"1 + ".repeat(20));
+
+        assert_eq!(nodes[0].get_name(), format!("{}...", &"1 + ".repeat(10)));
+    }
+}
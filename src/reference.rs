@@ -0,0 +1,162 @@
+use crate::annotations::AnnotationRegistry;
+use crate::navigation::{definition_sites, DefinitionSite};
+use crate::script::SourceDetails;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReferenceFormat {
+    Markdown,
+    Html,
+}
+
+/// Page listing every `@Def` in the given sources, with its documentation and source text.
+/// Sources without definitions are left out.
+pub fn reference_page(sources: &[&SourceDetails], registry: &AnnotationRegistry, format: ReferenceFormat) -> String {
+    let sections: Vec<(&String, Vec<DefinitionSite>)> = sources
+        .iter()
+        .map(|source| (source.name(), definition_sites(source.name(), source.text(), registry)))
+        .filter(|(_, sites)| !sites.is_empty())
+        .collect();
+
+    match format {
+        ReferenceFormat::Markdown => markdown(&sections),
+        ReferenceFormat::Html => html(&sections),
+    }
+}
+
+fn markdown(sections: &[(&String, Vec<DefinitionSite>)]) -> String {
+    let mut page = String::from("# Reference\n");
+
+    for (source, sites) in sections {
+        page.push_str(&format!("\n## {}\n", source));
+
+        for site in sites {
+            page.push_str(&format!("\n### {}\n\n", site.name()));
+            if let Some(documentation) = site.documentation() {
+                page.push_str(&format!("{}\n\n", documentation));
+            }
+            page.push_str(&format!("```garnish\n{}\n```\n", site.text()));
+        }
+    }
+
+    page
+}
+
+fn html(sections: &[(&String, Vec<DefinitionSite>)]) -> String {
+    let mut page = String::from("<h1>Reference</h1>\n");
+
+    for (source, sites) in sections {
+        page.push_str(&format!("<section>\n<h2>{}</h2>\n", escape(source)));
+
+        for site in sites {
+            page.push_str(&format!("<h3 id=\"{0}\">{0}</h3>\n", escape(site.name())));
+            if let Some(documentation) = site.documentation() {
+                page.push_str(&format!("<p>{}</p>\n", escape(documentation)));
+            }
+            page.push_str(&format!("<pre><code>{}</code></pre>\n", escape(site.text())));
+        }
+
+        page.push_str("</section>\n");
+    }
+
+    page
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reference::ReferenceFormat;
+    use crate::script::GarnishScript;
+
+    fn script() -> GarnishScript {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Doc \"Adds five to input\"\n@Def add_5 { $ + 5 }\n\n@Def undocumented { $ }\n\nadd_5 ~ 10".to_string(),
+        );
+        script.include("helpers".to_string(), "@Doc \"Halves input\"\n\n@Def half { $ / 2 }".to_string());
+        script.include("empty".to_string(), "5".to_string());
+        script
+    }
+
+    #[test]
+    fn documentation_after_compile() {
+        let mut script = script();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_error(), None);
+        assert_eq!(script.get_execution_result(0), Some("15".to_string()));
+        assert_eq!(script.get_documentation("add_5".to_string()), Some("Adds five to input".to_string()));
+        assert_eq!(script.get_documentation("half".to_string()), Some("Halves input".to_string()));
+        assert_eq!(script.get_documentation("undocumented".to_string()), None);
+    }
+
+    #[test]
+    fn documentation_removed() {
+        let mut script = GarnishScript::new("main".to_string(), "@Doc \"Five\"\n@Def five { 5 }".to_string());
+        script.compile();
+        assert_eq!(script.get_documentation("five".to_string()), Some("Five".to_string()));
+
+        script.set_text("@Def five { 5 }".to_string());
+        script.compile();
+        assert_eq!(script.get_documentation("five".to_string()), None);
+    }
+
+    #[test]
+    fn documentation_escapes() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            r#"@Doc "Tab\there \\ backslash"
+@Def greet { 5 }
+
+@Doc """Quoted "text" here"""
+@Def quoted { 5 }"#
+                .to_string(),
+        );
+        script.compile();
+
+        assert_eq!(script.get_documentation("greet".to_string()), Some("Tab\there \\ backslash".to_string()));
+        assert_eq!(script.get_documentation("quoted".to_string()), Some("Quoted \"text\" here".to_string()));
+    }
+
+    #[test]
+    fn doc_must_precede_definition() {
+        let mut script = GarnishScript::new("main".to_string(), "@Doc \"Lost\"\n\n10\n\n@Def five { 5 }".to_string());
+        script.compile();
+
+        assert_eq!(script.get_documentation("five".to_string()), None);
+    }
+
+    #[test]
+    fn hover_documentation() {
+        let script = script();
+        let hover = script.hover("half ~ 4".to_string(), 0, 0).unwrap();
+
+        assert_eq!(hover.get_documentation(), Some("Halves input".to_string()));
+        assert_eq!(hover.get_contents(), "@Def half { $ / 2 }");
+    }
+
+    #[test]
+    fn markdown_page() {
+        assert_eq!(
+            script().reference_page(ReferenceFormat::Markdown),
+            "# Reference\n\n## main\n\n### add_5\n\nAdds five to input\n\n```garnish\n@Def add_5 { $ + 5 }\n```\n\n### undocumented\n\n```garnish\n@Def undocumented { $ }\n```\n\n## helpers\n\n### half\n\nHalves input\n\n```garnish\n@Def half { $ / 2 }\n```\n"
+        );
+    }
+
+    #[test]
+    fn html_page() {
+        let page = script().reference_page(ReferenceFormat::Html);
+
+        assert!(page.starts_with("<h1>Reference</h1>\n<section>\n<h2>main</h2>\n"));
+        assert!(page.contains("<h3 id=\"half\">half</h3>\n<p>Halves input</p>\n<pre><code>@Def half { $ / 2 }</code></pre>\n"));
+        assert!(!page.contains("empty"));
+    }
+}
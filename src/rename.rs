@@ -0,0 +1,234 @@
+use crate::annotations::AnnotationRegistry;
+use crate::context::BrowserContext;
+use crate::navigation::{definition_sites, references, Location};
+use crate::script::SourceDetails;
+use crate::tokens::{end_position, offset_of};
+use garnish_lang::compiler::lex::{lex, TokenType};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Replacement of a range of text in a source.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    location: Location,
+    new_text: String,
+}
+
+#[wasm_bindgen]
+impl TextEdit {
+    pub fn get_location(&self) -> Location {
+        self.location.clone()
+    }
+
+    pub fn get_new_text(&self) -> String {
+        self.new_text.clone()
+    }
+}
+
+/// Edits renaming a `@Def` or include, and every reference to it, across all sources.
+///
+/// Names are global across sources so the new name can't already be used by another
+/// definition, include or constant, or by a parameter of a definition containing a reference.
+pub fn rename_edits(
+    old: &str,
+    new: &str,
+    context: &BrowserContext,
+    source: &SourceDetails,
+    includes: &[SourceDetails],
+    registry: &AnnotationRegistry,
+) -> Result<Vec<TextEdit>, String> {
+    let sources: Vec<&SourceDetails> = std::iter::once(source).chain(includes).collect();
+
+    match lex(new) {
+        Ok(tokens) if tokens.len() == 1 && tokens[0].get_token_type() == TokenType::Identifier => (),
+        _ => return Err(format!("`{}` is not a valid identifier", new)),
+    }
+
+    let is_definition = |name: &str| {
+        sources
+            .iter()
+            .flat_map(|s| definition_sites(s.name(), s.text(), registry))
+            .any(|site| site.name() == name)
+    };
+    let is_include = |name: &str| includes.iter().any(|include| include.name() == name);
+
+    if !is_definition(old) && !is_include(old) {
+        return Err(format!("No definition or include named `{}`", old));
+    }
+
+    let is_declared = |name: &str| {
+        sources
+            .iter()
+            .flat_map(|s| references(s.text(), registry))
+            .any(|reference| reference.is_definition() && reference.token().get_text() == name)
+    };
+
+    if is_declared(new)
+        || is_include(new)
+        || source.name() == new
+        || context.constants().any(|(name, _)| name == new)
+        || context.native_names().any(|name| name == new)
+    {
+        return Err(format!("Name `{}` is already in use", new));
+    }
+
+    let mut edits = vec![];
+    for source in sources {
+        let sites = definition_sites(source.name(), source.text(), registry);
+        for reference in references(source.text(), registry) {
+            let token = reference.token();
+            if token.get_text() == old {
+                let position = (token.get_line(), token.get_column());
+                let capturing = sites.iter().find(|site| {
+                    !reference.is_definition()
+                        && site.location().start() <= position
+                        && position < site.location().end()
+                        && site.parameters().iter().any(|parameter| parameter == new)
+                });
+                if let Some(site) = capturing {
+                    return Err(format!("Name `{}` is a parameter of `{}`", new, site.name()));
+                }
+
+                edits.push(TextEdit {
+                    location: Location::new(
+                        source.name(),
+                        (token.get_line(), token.get_column()),
+                        end_position(token),
+                    ),
+                    new_text: new.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(edits)
+}
+
+/// Apply edits for the given source to its text. Edits for other sources are ignored.
+pub fn apply_edits(source: &SourceDetails, edits: &[TextEdit]) -> String {
+    let text = source.text();
+    let mut ranges: Vec<(usize, usize, &String)> = edits
+        .iter()
+        .filter(|edit| &edit.location.get_source() == source.name())
+        .map(|edit| {
+            let (start, end) = (edit.location.start(), edit.location.end());
+            (
+                offset_of(text, start.0, start.1),
+                offset_of(text, end.0, end.1),
+                &edit.new_text,
+            )
+        })
+        .collect();
+
+    // apply from the end so earlier offsets stay valid
+    ranges.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+
+    let mut result = text.clone();
+    for (start, end, new_text) in ranges {
+        result.replace_range(start..end, new_text);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    #[test]
+    fn edits_across_sources() {
+        let mut script = GarnishScript::new("main".to_string(), "half ~ 10\n\nhalf ~ 4".to_string());
+        script.include("helpers".to_string(), "@Def half { $ / 2 }\n\n(half = 5).half".to_string());
+
+        let edits = script.rename_symbol("half".to_string(), "halve".to_string()).unwrap();
+        let positions: Vec<(String, (usize, usize))> = edits
+            .iter()
+            .map(|e| (e.get_location().get_source(), e.get_location().start()))
+            .collect();
+
+        assert_eq!(
+            positions,
+            vec![
+                ("main".to_string(), (0, 0)),
+                ("main".to_string(), (2, 0)),
+                ("helpers".to_string(), (0, 5)),
+            ]
+        );
+        assert!(edits.iter().all(|e| e.get_new_text() == "halve"));
+    }
+
+    #[test]
+    fn apply_rename() {
+        let mut script = GarnishScript::new("main".to_string(), "half ~ 10".to_string());
+        script.include("helpers".to_string(), "@Def half { $ / 2 }\n\nhalf ~ 8".to_string());
+
+        script.apply_rename_symbol("half".to_string(), "halve".to_string()).unwrap();
+
+        assert_eq!(script.get_text(), "halve ~ 10");
+        script.compile();
+        script.execute();
+        assert_eq!(script.get_execution_result(0), Some("5".to_string()));
+    }
+
+    #[test]
+    fn rename_include() {
+        let mut script = GarnishScript::new("main".to_string(), "add_5 ~ 10".to_string());
+        script.include("add_5".to_string(), "$ + 5".to_string());
+
+        script.apply_rename_symbol("add_5".to_string(), "plus_5".to_string()).unwrap();
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_text(), "plus_5 ~ 10");
+        assert_eq!(script.get_execution_result(0), Some("15".to_string()));
+        assert_eq!(
+            script.rename_symbol("plus_5".to_string(), "add_5".to_string()),
+            Err("`plus_5` is an include, use apply_rename_symbol to rename it".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_renames() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def one { 1 }\n\n@Def two { 2 }".to_string());
+        script.include("three".to_string(), "3".to_string());
+
+        assert_eq!(
+            script.rename_symbol("missing".to_string(), "other".to_string()),
+            Err("No definition or include named `missing`".to_string())
+        );
+        assert_eq!(
+            script.rename_symbol("one".to_string(), "two".to_string()),
+            Err("Name `two` is already in use".to_string())
+        );
+        assert_eq!(
+            script.rename_symbol("one".to_string(), "three".to_string()),
+            Err("Name `three` is already in use".to_string())
+        );
+        assert_eq!(
+            script.rename_symbol("one".to_string(), "Math::PI".to_string()),
+            Err("Name `Math::PI` is already in use".to_string())
+        );
+        assert_eq!(
+            script.rename_symbol("one".to_string(), "not valid".to_string()),
+            Err("`not valid` is not a valid identifier".to_string())
+        );
+    }
+
+    #[test]
+    fn parameter_capture() {
+        let script = GarnishScript::new(
+            "main".to_string(),
+            "@Def half { $ / 2 }\n\n@Def f(x) { half ~ x }\n\n@Const Limit { 10 }\n\nf ~ 8".to_string(),
+        );
+
+        assert_eq!(
+            script.rename_symbol("half".to_string(), "x".to_string()),
+            Err("Name `x` is a parameter of `f`".to_string())
+        );
+        assert_eq!(
+            script.rename_symbol("half".to_string(), "Limit".to_string()),
+            Err("Name `Limit` is already in use".to_string())
+        );
+        assert!(script.rename_symbol("f".to_string(), "x".to_string()).is_ok());
+    }
+}
@@ -1,14 +1,32 @@
-use crate::compile::compile_source_into_data;
+use crate::annotations::{AnnotationHandler, AnnotationRegistry};
+use crate::ast::annotated_ast;
+use crate::catalog::{Capabilities, Module};
+use crate::compile::{compile_source_into_data, evaluate_constants};
+use crate::completion::{completions, Completion};
 use crate::context::BrowserContext;
+use crate::disassembly::{disassemble, Disassembly};
+use crate::display::{format_value, FormatOptions};
+use crate::execution::{run, run_expression, DEFAULT_EXECUTION_LIMIT};
+use crate::history::{ExecutionHistory, ExecutionInfo, ExecutionRecord, RetentionPolicy};
+use crate::json::json_of;
+use crate::library::time::Clock;
+use crate::lint::{lint, Diagnostic};
+use crate::navigation::{definition, hover, Hover, Location};
+use crate::outline::{annotated_outline, OutlineNode};
+use crate::reference::{reference_page, ReferenceFormat};
+use crate::rename::{apply_edits, rename_edits, TextEdit};
+use crate::testing::TestResult;
+use crate::utils::now_millis;
 use garnish_lang::compiler::build::build_with_data;
 use garnish_lang::compiler::lex::lex;
 use garnish_lang::compiler::parse::parse;
-use garnish_lang::simple::{SimpleGarnishData, SimpleGarnishRuntime, SimpleRuntimeState};
-use garnish_lang::{GarnishData, GarnishRuntime, Instruction};
+use garnish_lang::simple::{SimpleData, SimpleGarnishData};
+use garnish_lang::GarnishData;
 use garnish_lang_utilities::data::copy_data_at_to_data;
-use garnish_lang_utilities::simple_expression_data_format;
+use garnish_lang_annotations_collector::{PartBehavior, PartParser};
+use js_sys::Function;
 use wasm_bindgen::prelude::wasm_bindgen;
-use web_sys::console;
+use wasm_bindgen::JsValue;
 
 #[wasm_bindgen]
 pub struct SourceDetails {
@@ -56,24 +74,37 @@ pub struct GarnishScript {
     include: Vec<SourceDetails>,
     data: SimpleGarnishData,
     error: Option<String>,
-    executions: Vec<SimpleGarnishData>,
+    diagnostics: Vec<Diagnostic>,
+    executions: ExecutionHistory,
     context: BrowserContext,
     execution_limit: usize,
+    format_options: FormatOptions,
+    annotations: AnnotationRegistry,
+    random_seed: Option<u32>,
 }
 
 #[wasm_bindgen]
 impl GarnishScript {
     #[wasm_bindgen(constructor)]
     pub fn new(name: String, text: String) -> Self {
+        GarnishScript::with_capabilities(name, text, Capabilities::Full)
+    }
+
+    /// Script that can only use built-ins within the given capabilities.
+    pub fn with_capabilities(name: String, text: String, capabilities: Capabilities) -> Self {
         GarnishScript {
             source: SourceDetails::new(name, text),
             input: None,
             include: vec![],
             data: SimpleGarnishData::new(),
             error: None,
-            executions: vec![],
-            context: BrowserContext::new(),
-            execution_limit: 10000,
+            diagnostics: vec![],
+            executions: ExecutionHistory::new(),
+            context: BrowserContext::with_capabilities(capabilities),
+            execution_limit: DEFAULT_EXECUTION_LIMIT,
+            format_options: FormatOptions::new(),
+            annotations: AnnotationRegistry::new(),
+            random_seed: None,
         }
     }
 
@@ -101,25 +132,51 @@ impl GarnishScript {
         self.input = Some(input);
     }
 
+    pub fn get_format_options(&self) -> FormatOptions {
+        self.format_options.clone()
+    }
+
+    /// Options used when formatting execution results and side effect output.
+    /// Results are formatted when they run, so earlier executions keep the options they ran with.
+    pub fn set_format_options(&mut self, options: FormatOptions) {
+        self.format_options = options;
+    }
+
     pub fn get_error(&self) -> Option<String> {
         self.error.clone()
     }
 
+    /// Errors and warnings from the last successful compile, distinguished by their severity.
+    ///
+    /// Errors, such as wrong argument counts or built-ins the capabilities don't allow, are code that won't run
+    /// as written. Warnings are advisory.
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
+
     pub fn include(&mut self, name: String, text: String) {
         self.include.push(SourceDetails::new(name, text))
     }
 
     pub fn get_execution_result(&self, execution_index: usize) -> Option<String> {
-        self.executions.get(execution_index).and_then(|execution| {
-            execution.get_current_value().and_then(|v| {
-                Some(simple_expression_data_format(
-                    v,
-                    execution,
-                    &self.context,
-                    0,
-                ))
-            })
-        })
+        self.executions
+            .get(execution_index)
+            .and_then(|record| record.result().cloned())
+    }
+
+    /// Result of an execution as JSON, None when it has no JSON form or its data wasn't retained.
+    pub fn get_execution_result_json(&self, execution_index: usize) -> Option<String> {
+        self.executions
+            .get(execution_index)
+            .and_then(|record| record.data())
+            .and_then(|data| json_of(data.get_current_value()?, data, &self.context))
+            .map(|value| value.to_string())
+    }
+
+    pub fn get_execution_info(&self, execution_index: usize) -> Option<ExecutionInfo> {
+        self.executions
+            .get(execution_index)
+            .map(|record| record.info().clone())
     }
 
     pub fn get_execution_count(&self) -> u32 {
@@ -127,35 +184,224 @@ impl GarnishScript {
     }
 
     pub fn clear_executions(&mut self) {
-        self.executions = vec![];
+        self.executions.clear();
+    }
+
+    pub fn get_history_limit(&self) -> Option<usize> {
+        self.executions.limit()
+    }
+
+    /// Keep only the most recent `limit` executions, or all of them when `None`.
+    pub fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.executions.set_limit(limit);
+    }
+
+    pub fn get_random_seed(&self) -> Option<u32> {
+        self.random_seed
+    }
+
+    /// Seed for `Random::` functions, restarted for every compile, execution and test run
+    /// so the same seed always produces the same values.
+    /// When `None` the current time is used, or 0 without full capabilities.
+    pub fn set_random_seed(&mut self, seed: Option<u32>) {
+        self.random_seed = seed;
+    }
+
+    pub fn get_fixed_time(&self) -> Option<f64> {
+        match self.context.clock() {
+            Clock::Fixed(millis) => Some(millis),
+            Clock::System => None,
+        }
+    }
+
+    /// Milliseconds since the Unix epoch returned by `Time::now`, so results don't change between runs.
+    /// When `None` the current time is used, or 0 without full capabilities.
+    pub fn set_fixed_time(&mut self, millis: Option<f64>) {
+        self.context.set_clock(millis.map_or(Clock::System, Clock::Fixed));
+    }
+
+    pub fn get_retention_policy(&self) -> RetentionPolicy {
+        self.executions.policy()
+    }
+
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) {
+        self.executions.set_policy(policy);
+    }
+
+    pub fn get_capabilities(&self) -> Capabilities {
+        self.context.capabilities()
+    }
+
+    /// Catalog of built-in modules, with their members and whether they're enabled.
+    pub fn get_modules(&self) -> Vec<Module> {
+        self.context.modules().to_vec()
+    }
+
+    /// Enable or disable all members of a built-in module, such as `Random`.
+    /// Members of a disabled module are left unresolved in scripts compiled afterwards.
+    pub fn set_module_enabled(&mut self, name: String, enabled: bool) -> Result<(), String> {
+        self.context.set_module_enabled(&name, enabled)
+    }
+
+    /// Completion candidates for the identifier at the given zero based position in text.
+    pub fn completions(&self, text: String, line: usize, column: usize) -> Vec<Completion> {
+        completions(&text, line, column, &self.context, &self.source, &self.include, &self.annotations)
+    }
+
+    /// Information about the identifier at the given zero based position in text.
+    pub fn hover(&self, text: String, line: usize, column: usize) -> Option<Hover> {
+        hover(
+            &text,
+            line,
+            column,
+            &self.context,
+            &self.source,
+            &self.include,
+            &self.annotations,
+            &self.format_options,
+        )
+    }
+
+    /// Location of the `@Def` or include referred to by the identifier at the given zero based position in text.
+    pub fn definition(&self, text: String, line: usize, column: usize) -> Option<Location> {
+        definition(&text, line, column, &self.source, &self.include, &self.annotations)
+    }
+
+    /// Outline of text as the script's source, with registered annotations left out.
+    pub fn outline(&self, text: String) -> Vec<OutlineNode> {
+        annotated_outline(&SourceDetails::new(self.source.name.clone(), text), &self.annotations)
+    }
+
+    /// Parse tree of text as JSON, with registered annotations left out of the root expression.
+    pub fn get_ast(&self, text: String) -> Result<String, String> {
+        annotated_ast(&text, &self.annotations)
+    }
+
+    /// Handle an additional annotation with a JavaScript function.
+    ///
+    /// The given number of tokens following the annotation are passed to the function as text.
+    /// A string returned from the function is recorded as metadata under the annotation's name.
+    /// Without full capabilities the function is never called and using the annotation is a compile error.
+    /// Built in annotations can't be replaced.
+    pub fn register_js_annotation(
+        &mut self,
+        annotation: String,
+        token_count: usize,
+        callback: Function,
+    ) -> Result<(), String> {
+        let key = annotation.clone();
+        let capabilities = self.context.capabilities();
+        let handler = AnnotationHandler::new(&annotation, move |block, scope| {
+            if capabilities < Capabilities::Full {
+                return Err(format!(
+                    "`{}` calls the host and isn't allowed with {:?} capabilities",
+                    key, capabilities
+                ));
+            }
+
+            let text: String = block
+                .parts()
+                .iter()
+                .flatten()
+                .map(|t| t.get_text().as_str())
+                .collect();
+
+            let result = callback
+                .call1(&JsValue::NULL, &JsValue::from_str(text.trim()))
+                .map_err(|e| e.as_string().unwrap_or(format!("{:?}", e)))?;
+
+            if let Some(value) = result.as_string() {
+                scope.add_metadata(&key, &value);
+            }
+
+            Ok(())
+        })
+        .part(PartParser::new(PartBehavior::TokenCount(token_count)));
+
+        self.annotations.register(handler)
+    }
+
+    /// Values recorded by annotation handlers during the last compile.
+    pub fn get_metadata(&self, key: String) -> Vec<String> {
+        self.context.get_metadata(&key).to_vec()
+    }
+
+    /// Documentation from the `@Doc` annotation of a compiled `@Def`.
+    pub fn get_documentation(&self, name: String) -> Option<String> {
+        self.context.get_documentation(&name).cloned()
+    }
+
+    /// Listing of the data produced by the last compile.
+    pub fn disassemble(&self) -> Disassembly {
+        disassemble(&self.data, &self.context)
+    }
+
+    /// Markdown or HTML page listing all `@Def`s across the source and includes.
+    pub fn reference_page(&self, format: ReferenceFormat) -> String {
+        let sources: Vec<&SourceDetails> = std::iter::once(&self.source).chain(&self.include).collect();
+        reference_page(&sources, &self.annotations, format)
+    }
+
+    /// Edits renaming a `@Def` and all references to it, across the source and includes.
+    ///
+    /// An include's name isn't part of any text, so edits can't rename one.
+    /// Includes are refused, use `apply_rename_symbol` to rename them.
+    pub fn rename_symbol(&self, old: String, new: String) -> Result<Vec<TextEdit>, String> {
+        if self.include.iter().any(|include| include.name == old) {
+            return Err(format!("`{}` is an include, use apply_rename_symbol to rename it", old));
+        }
+
+        rename_edits(&old, &new, &self.context, &self.source, &self.include, &self.annotations)
+    }
+
+    /// Rename a `@Def` or include, updating the text of all sources and the name of a renamed include.
+    pub fn apply_rename_symbol(&mut self, old: String, new: String) -> Result<(), String> {
+        let edits = rename_edits(&old, &new, &self.context, &self.source, &self.include, &self.annotations)?;
+
+        self.source.text = apply_edits(&self.source, &edits);
+        for include in self.include.iter_mut() {
+            include.text = apply_edits(include, &edits);
+            if include.name == old {
+                include.name = new.clone();
+            }
+        }
+
+        Ok(())
     }
 
     pub fn compile(&mut self) {
         self.data = SimpleGarnishData::new_custom();
+        self.diagnostics = vec![];
+        self.context.clear_definitions();
+        self.context.clear_tests();
+        self.context.clear_metadata();
+        self.reset_random();
+
+        if let Err(e) = compile_source_into_data(&self.source, &mut self.data, &mut self.context, &self.annotations) {
+            self.error = Some(format!("Error compiling {}: {}", self.source.name(), e));
+            return;
+        }
 
-        match compile_source_into_data(&self.source, &mut self.data, &mut self.context) {
-            Err(e) => {
+        for source in &self.include {
+            if let Err(e) = compile_source_into_data(source, &mut self.data, &mut self.context, &self.annotations) {
                 self.error = Some(format!("Error compiling {}: {}", self.source.name(), e));
                 return;
             }
-            Ok(()) => (),
         }
 
-        for source in &self.include {
-            match compile_source_into_data(source, &mut self.data, &mut self.context) {
-                Err(e) => {
-                    self.error = Some(format!("Error compiling {}: {}", self.source.name(), e));
-                    return;
-                }
-                Ok(()) => (),
-            }
+        if let Err(e) = evaluate_constants(&self.data, &mut self.context, self.execution_limit) {
+            self.error = Some(format!("Error compiling {}: {}", self.source.name(), e));
+            return;
         }
+
+        self.diagnostics = lint(&self.context, &self.source, &self.include, &self.annotations);
     }
 
     pub fn execute(&mut self) {
+        self.reset_random();
         let mut execution_data = self.data.clone();
         let input_addr = match self.make_input() {
-            Err(e) if e == String::from("No Input") => 0,
+            Err(e) if e == "No Input" => 0,
             Err(e) => {
                 self.error = Some(e);
                 return;
@@ -170,57 +416,77 @@ impl GarnishScript {
                         self.error = Some(e.to_string());
                         return;
                     }
-                    Ok(i) => i,
+                    Ok(i) => {
+                        // names made while evaluating the input, such as parsed JSON keys
+                        for (symbol, name) in data.get_data().symbol_to_name() {
+                            execution_data.get_data_mut().insert_symbol(*symbol, name.clone());
+                        }
+                        i
+                    }
                 },
             },
         };
 
-        match execution_data.push_value_stack(input_addr) {
-            Err(e) => {
-                self.error = Some(e.to_string());
-                return;
-            }
-            Ok(()) => (),
+        if let Err(e) = execution_data.push_value_stack(input_addr) {
+            self.error = Some(e.to_string());
+            return;
         }
 
-        let mut runtime = SimpleGarnishRuntime::new(execution_data);
+        let (data, count, error) = run(
+            execution_data,
+            &mut self.context,
+            &self.format_options,
+            self.execution_limit,
+        );
 
-        let limit = self.execution_limit;
-        let mut count = 0;
+        let result = data
+            .get_current_value()
+            .map(|v| format_value(v, &data, &self.context, &self.format_options));
 
-        loop {
-            runtime.get_data().get_current_instruction().map(|(instruction, _data)| match instruction {
-                Instruction::EndSideEffect => {
-                    let formatted = runtime.get_data().get_registers().last()
-                        .map(|addr| simple_expression_data_format(*addr, runtime.get_data(), &self.context, 0))
-                        .unwrap_or("[Side Effect did not result in a value".to_string());
+        if error.is_some() {
+            self.error = error.clone();
+        }
 
-                    console::log_1(&formatted.into());
-                }
-                _ => ()
-            });
+        let info = ExecutionInfo::new(now_millis(), count, self.input.clone(), error);
+        self.executions.push(ExecutionRecord::new(info, result, data));
+    }
 
-            match runtime.execute_current_instruction(Some(&mut self.context)) {
-                Err(e) => {
-                    self.error = Some(e.get_message().clone());
-                    return;
-                }
-                Ok(data) => match data.get_state() {
-                    SimpleRuntimeState::Running => (),
-                    SimpleRuntimeState::End => break,
-                },
-            }
+    /// Execute each `@Test` from the last compile, passing when its result isn't False or Unit.
+    pub fn run_tests(&mut self) -> Vec<TestResult> {
+        let tests = self.context.tests().to_vec();
+        self.reset_random();
 
-            count += 1;
-            if count >= limit {
-                self.error = Some(
-                    "Instruction execution limit reached. Possibly an infinite loop.".to_string(),
+        tests
+            .into_iter()
+            .map(|(name, expression_index)| {
+                let (data, _, error) = run_expression(
+                    &self.data,
+                    expression_index,
+                    &mut self.context,
+                    &self.format_options,
+                    self.execution_limit,
                 );
-                break;
-            }
-        }
 
-        self.executions.push(runtime.get_data_owned());
+                let value = data.get_current_value();
+                let passed = error.is_none()
+                    && value
+                        .and_then(|v| data.get_data().get(v))
+                        .map(|v| !matches!(v, SimpleData::False | SimpleData::Unit))
+                        .unwrap_or(false);
+                let formatted = value.map(|v| format_value(v, &data, &self.context, &self.format_options));
+
+                TestResult::new(name, passed, formatted, error)
+            })
+            .collect()
+    }
+
+    fn reset_random(&mut self) {
+        let seed = match (self.random_seed, self.context.capabilities()) {
+            (Some(seed), _) => u64::from(seed),
+            (None, Capabilities::Full) => now_millis() as u64,
+            (None, _) => 0,
+        };
+        self.context.set_random_seed(seed);
     }
 
     fn make_input(&mut self) -> Result<SimpleGarnishData, String> {
@@ -251,56 +517,29 @@ impl GarnishScript {
 
         let mut data = SimpleGarnishData::new_custom();
 
-        match build_with_data(
+        if let Err(e) = build_with_data(
             parse_result.get_root(),
             parse_result.get_nodes().clone(),
             &mut data,
         ) {
-            Err(e) => {
-                return Err(e.get_message().clone());
-            }
-            Ok(_) => {}
+            return Err(e.get_message().clone());
         }
 
-        return Ok(data);
+        Ok(data)
     }
 
     fn execute_data(&mut self, mut data: SimpleGarnishData) -> SimpleGarnishData {
-        match data.push_value_stack(0) {
-            Err(e) => {
-                self.error = Some(e.to_string());
-                return data;
-            }
-            Ok(()) => {}
+        if let Err(e) = data.push_value_stack(0) {
+            self.error = Some(e.to_string());
+            return data;
         }
 
-        let mut runtime = SimpleGarnishRuntime::new(data);
-
-        let limit = self.execution_limit;
-        let mut count = 0;
-
-        loop {
-            match runtime.execute_current_instruction(Some(&mut self.context)) {
-                Err(e) => {
-                    self.error = Some(e.get_message().clone());
-                    break;
-                }
-                Ok(info) => match info.get_state() {
-                    SimpleRuntimeState::Running => (),
-                    SimpleRuntimeState::End => break,
-                },
-            }
-
-            count += 1;
-            if count >= limit {
-                self.error = Some(
-                    "Instruction execution limit reached. Possibly an infinite loop.".to_string(),
-                );
-                break;
-            }
+        let (data, _, error) = run(data, &mut self.context, &self.format_options, self.execution_limit);
+        if error.is_some() {
+            self.error = error;
         }
 
-        return runtime.get_data_owned();
+        data
     }
 }
 
@@ -313,14 +552,21 @@ impl GarnishScript {
     }
 
     pub fn get_execution(&self, index: usize) -> Option<&SimpleGarnishData> {
-        self.executions.get(index)
+        self.executions.get(index).and_then(|record| record.data())
+    }
+
+    /// Handle an additional annotation during compilation. Built in annotations can't be replaced.
+    pub fn register_annotation(&mut self, handler: AnnotationHandler) -> Result<(), String> {
+        self.annotations.register(handler)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::display::FormatOptions;
+    use crate::history::RetentionPolicy;
     use crate::script::GarnishScript;
-    use garnish_lang::simple::{SimpleData, SimpleNumber};
+    use garnish_lang::simple::{symbol_value, SimpleData, SimpleGarnishData, SimpleNumber};
     use garnish_lang::GarnishData;
 
     #[test]
@@ -426,6 +672,22 @@ mod tests {
         assert_eq!(script.get_execution_count(), 0);
     }
 
+    #[test]
+    fn execution_result_json() {
+        let mut script = GarnishScript::new(
+            "test_one".to_string(),
+            "List::map ~ ((Json::parse ~ $).values, { $ * 2 }), :source = \"input\"".to_string(),
+        );
+        script.set_input(r#""""{"values": [1, 2]}""""#.to_string());
+        script.compile();
+        script.execute();
+
+        assert_eq!(
+            script.get_execution_result_json(0),
+            Some("[[2,4],{\"source\":\"input\"}]".to_string())
+        );
+    }
+
     #[test]
     fn execute_with_input() {
         let mut script = GarnishScript::new("test_one".to_string(), "$ + 5".to_string());
@@ -531,6 +793,47 @@ mod tests {
         )
     }
 
+    #[test]
+    fn constants_use_execution_limit() {
+        let mut script = GarnishScript::new(
+            "test_one".to_string(),
+            "@Const Total { List::sum ~ (List::map ~ (List::range ~ 20, { $ * 2 })) }\n\nTotal".to_string(),
+        );
+        script.compile();
+        assert_eq!(script.error, None);
+
+        script.execution_limit = 10;
+        script.compile();
+        assert_eq!(
+            script.error,
+            Some("Error compiling test_one: Error evaluating @Const Total: Instruction execution limit reached. Possibly an infinite loop.".to_string())
+        );
+    }
+
+    #[test]
+    fn runtime_symbol_names_kept_in_data() {
+        let mut script = GarnishScript::new(
+            "test_one".to_string(),
+            r#"$, Json::parse ~ """{"from_script": 1}""", Regex::captures ~ ("ab", "(?<from_regex>b)")"#.to_string(),
+        );
+        script.set_input(r#"Json::parse ~ """{"from_input": 2}""""#.to_string());
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_error(), None);
+        assert_eq!(
+            script.get_execution_result(0),
+            Some(r#"(:from_input = 2), (:from_script = 1), ("b", :from_regex = "b")"#.to_string())
+        );
+        assert_eq!(
+            script.get_execution_result_json(0),
+            Some(r#"[{"from_input":2},{"from_script":1},["b",{"from_regex":"b"}]]"#.to_string())
+        );
+        for name in ["from_input", "from_script", "from_regex"] {
+            assert_eq!(script.context.symbol_name(symbol_value(name), &SimpleGarnishData::new()), None);
+        }
+    }
+
     #[test]
     fn symbol_formats_to_name() {
         let mut script = GarnishScript::new("test_one".to_string(), ":my_symbol".to_string());
@@ -542,4 +845,91 @@ mod tests {
             ":my_symbol"
         )
     }
+
+    #[test]
+    fn execution_info() {
+        let mut script = GarnishScript::new("test_one".to_string(), "$ + 5".to_string());
+        script.set_input("10".to_string());
+        script.compile();
+        script.execute();
+
+        let info = script.get_execution_info(0).unwrap();
+        assert_eq!(info.get_input(), Some("10".to_string()));
+        assert_eq!(info.get_error(), None);
+        assert!(info.get_instruction_count() > 0);
+        assert!(info.get_timestamp() > 0.0);
+        assert!(script.get_execution_info(1).is_none());
+    }
+
+    #[test]
+    fn execution_info_with_error() {
+        let mut script = GarnishScript::new("test_one".to_string(), "$? ^~ $ + 5".to_string());
+        script.compile();
+        script.execute();
+
+        assert_eq!(
+            script.get_execution_info(0).unwrap().get_error(),
+            Some("Instruction execution limit reached. Possibly an infinite loop.".to_string())
+        );
+        assert_eq!(script.get_execution_info(0).unwrap().get_instruction_count(), 10000);
+    }
+
+    #[test]
+    fn input_after_limit_reached() {
+        let mut script = GarnishScript::new("test_one".to_string(), "$? ^~ $ + 5".to_string());
+        script.compile();
+        script.execute();
+
+        script.set_text("$".to_string());
+        script.set_input("List::map ~ ((1, 2), { $ * 2 })".to_string());
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_execution_info(1).unwrap().get_error(), None);
+        assert_eq!(script.get_execution_result(1), Some("2, 4".to_string()));
+    }
+
+    #[test]
+    fn history_limit() {
+        let mut script = GarnishScript::new("test_one".to_string(), "$ + 5".to_string());
+        script.set_history_limit(Some(2));
+        script.compile();
+        for input in ["1", "2", "3"] {
+            script.set_input(input.to_string());
+            script.execute();
+        }
+
+        assert_eq!(script.get_execution_count(), 2);
+        assert_eq!(script.get_execution_result(0), Some("7".to_string()));
+        assert_eq!(script.get_execution_result(1), Some("8".to_string()));
+    }
+
+    #[test]
+    fn results_only_retention() {
+        let mut script = GarnishScript::new("test_one".to_string(), "5 + 5".to_string());
+        script.set_retention_policy(RetentionPolicy::ResultsOnly);
+        script.compile();
+        script.execute();
+
+        assert!(script.get_execution(0).is_none());
+        assert_eq!(script.get_execution_result(0), Some("10".to_string()));
+    }
+
+    #[test]
+    fn results_keep_format_options() {
+        let mut script = GarnishScript::new("test_one".to_string(), "1, 2, 3".to_string());
+        script.set_retention_policy(RetentionPolicy::LastFull);
+        script.compile();
+        script.execute();
+
+        let mut options = FormatOptions::new();
+        options.set_max_list_length(Some(1));
+        script.set_format_options(options);
+        script.execute();
+        script.execute();
+
+        assert_eq!(script.get_execution_result(0), Some("1, 2, 3".to_string()));
+        assert_eq!(script.get_execution_result(1), Some("1, ...".to_string()));
+        assert_eq!(script.get_execution_result(2), Some("1, ...".to_string()));
+    }
 }
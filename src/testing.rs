@@ -0,0 +1,107 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Outcome of running a single `@Test` expression.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    name: String,
+    passed: bool,
+    value: Option<String>,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl TestResult {
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_passed(&self) -> bool {
+        self.passed
+    }
+
+    /// Formatted result of the test expression, if one was produced.
+    pub fn get_value(&self) -> Option<String> {
+        self.value.clone()
+    }
+
+    /// Runtime error that stopped the test.
+    pub fn get_error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+impl TestResult {
+    pub fn new(name: String, passed: bool, value: Option<String>, error: Option<String>) -> Self {
+        TestResult {
+            name,
+            passed,
+            value,
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::GarnishScript;
+
+    #[test]
+    fn pass_and_fail() {
+        let mut script = GarnishScript::new(
+            "main".to_string(),
+            "@Def add_5 { $ + 5 }\n\n@Test adds_five { (add_5 ~ 5) == 10 }\n\n@Test wrong { (add_5 ~ 5) == 11 }\n\n@Test value { add_5 ~ 1 }\n\n100".to_string(),
+        );
+        script.compile();
+
+        let results = script.run_tests();
+        let outcomes: Vec<(String, bool, Option<String>)> = results
+            .iter()
+            .map(|r| (r.get_name(), r.get_passed(), r.get_value()))
+            .collect();
+
+        assert_eq!(
+            outcomes,
+            vec![
+                ("adds_five".to_string(), true, Some("True".to_string())),
+                ("wrong".to_string(), false, Some("False".to_string())),
+                ("value".to_string(), true, Some("6".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn excluded_from_root() {
+        let mut script = GarnishScript::new("main".to_string(), "@Test unit { () }\n\n5 + 5".to_string());
+        script.compile();
+        script.execute();
+
+        assert_eq!(script.get_execution_result(0), Some("10".to_string()));
+
+        let results = script.run_tests();
+        assert!(!results[0].get_passed());
+        assert_eq!(results[0].get_value(), Some("()".to_string()));
+    }
+
+    #[test]
+    fn tests_in_includes() {
+        let mut script = GarnishScript::new("main".to_string(), "5".to_string());
+        script.include("helpers".to_string(), "@Def half { $ / 2 }\n\n@Test halves { (half ~ 10) == 5 }".to_string());
+        script.compile();
+        script.compile();
+
+        let results = script.run_tests();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].get_passed());
+    }
+
+    #[test]
+    fn execution_error() {
+        let mut script = GarnishScript::new("main".to_string(), "@Def forever { forever ~ $ }\n\n@Test endless { forever ~ 1 }".to_string());
+        script.compile();
+
+        let results = script.run_tests();
+        assert!(!results[0].get_passed());
+        assert!(results[0].get_error().is_some());
+    }
+}
@@ -0,0 +1,301 @@
+use garnish_lang::compiler::error::CompilerError;
+use garnish_lang::compiler::lex::{lex, Lexer, LexerToken, TokenType};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Broad grouping of token types, for coloring source text.
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TokenCategory {
+    Operator,
+    Punctuation,
+    Number,
+    String,
+    Symbol,
+    Identifier,
+    Constant,
+    Annotation,
+    Comment,
+    Whitespace,
+    Error,
+}
+
+impl From<TokenType> for TokenCategory {
+    fn from(token_type: TokenType) -> Self {
+        match token_type {
+            TokenType::Number => TokenCategory::Number,
+            TokenType::CharList | TokenType::ByteList => TokenCategory::String,
+            TokenType::Symbol => TokenCategory::Symbol,
+            TokenType::Identifier
+            | TokenType::PrefixIdentifier
+            | TokenType::SuffixIdentifier
+            | TokenType::InfixIdentifier => TokenCategory::Identifier,
+            TokenType::Value | TokenType::True | TokenType::False | TokenType::UnitLiteral => {
+                TokenCategory::Constant
+            }
+            TokenType::Annotation => TokenCategory::Annotation,
+            TokenType::LineAnnotation => TokenCategory::Comment,
+            TokenType::Whitespace | TokenType::Subexpression => TokenCategory::Whitespace,
+            TokenType::StartExpression
+            | TokenType::EndExpression
+            | TokenType::StartGroup
+            | TokenType::EndGroup
+            | TokenType::StartSideEffect
+            | TokenType::EndSideEffect
+            | TokenType::Comma => TokenCategory::Punctuation,
+            TokenType::Unknown => TokenCategory::Error,
+            _ => TokenCategory::Operator,
+        }
+    }
+}
+
+/// Token with its location in the source text.
+/// Lines and columns are zero based, end positions are exclusive.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    kind: String,
+    text: String,
+    category: TokenCategory,
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl SyntaxToken {
+    pub fn get_kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    pub fn get_text(&self) -> String {
+        self.text.clone()
+    }
+
+    pub fn get_category(&self) -> TokenCategory {
+        self.category
+    }
+
+    pub fn get_start_line(&self) -> usize {
+        self.start_line
+    }
+
+    pub fn get_start_column(&self) -> usize {
+        self.start_column
+    }
+
+    pub fn get_end_line(&self) -> usize {
+        self.end_line
+    }
+
+    pub fn get_end_column(&self) -> usize {
+        self.end_column
+    }
+
+    /// Lexing error message, only set on the trailing error token.
+    pub fn get_error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+impl SyntaxToken {
+    fn from_lexer_token(token: &LexerToken) -> Self {
+        let (end_line, end_column) = end_position(token);
+        SyntaxToken {
+            kind: format!("{:?}", token.get_token_type()),
+            text: token.get_text().clone(),
+            category: TokenCategory::from(token.get_token_type()),
+            start_line: token.get_line(),
+            start_column: token.get_column(),
+            end_line,
+            end_column,
+            error: None,
+        }
+    }
+
+    fn error(text: String, line: usize, column: usize, message: String) -> Self {
+        let (end_line, end_column) = advance_position(&text, line, column);
+        SyntaxToken {
+            kind: "Error".to_string(),
+            text,
+            category: TokenCategory::Error,
+            start_line: line,
+            start_column: column,
+            end_line,
+            end_column,
+            error: Some(message),
+        }
+    }
+}
+
+/// Lex source text into tokens for syntax highlighting.
+///
+/// When lexing fails, tokens up to the failure are returned followed by a single
+/// error token covering the rest of the text.
+#[wasm_bindgen]
+pub fn tokenize(text: String) -> Vec<SyntaxToken> {
+    match lex(&text) {
+        Ok(tokens) => tokens.iter().map(SyntaxToken::from_lexer_token).collect(),
+        Err(e) => {
+            let message = e.get_message().clone();
+            // without a position the error token covers the whole text
+            let (line, column) = error_position(e).unwrap_or((0, 0));
+
+            let mut tokens: Vec<SyntaxToken> = Lexer::new(&text)
+                .take_while(|t| (t.get_line(), t.get_column()) < (line, column))
+                .map(|t| SyntaxToken::from_lexer_token(&t))
+                .collect();
+
+            let offset = offset_of(&text, line, column);
+            tokens.push(SyntaxToken::error(
+                text[offset..].to_string(),
+                line,
+                column,
+                message,
+            ));
+
+            tokens
+        }
+    }
+}
+
+/// Identifier token containing, or directly followed by, the given line and column.
+pub fn identifier_at(tokens: &[LexerToken], line: usize, column: usize) -> Option<&LexerToken> {
+    tokens
+        .iter()
+        .filter(|t| t.get_token_type() == TokenType::Identifier && t.get_line() == line)
+        .find(|t| t.get_column() <= column && column <= end_position(t).1)
+}
+
+/// True for an identifier at `index` that is resolved by name,
+/// rather than accessed on a value after `.` or used as a key before `=`.
+pub fn is_resolved_identifier(tokens: &[LexerToken], index: usize) -> bool {
+    let is_significant = |t: &&LexerToken| {
+        !matches!(
+            t.get_token_type(),
+            TokenType::Whitespace | TokenType::Subexpression | TokenType::LineAnnotation
+        )
+    };
+
+    let after_period = tokens[..index]
+        .iter()
+        .rev()
+        .find(is_significant)
+        .map(|t| t.get_token_type() == TokenType::Period)
+        .unwrap_or(false);
+    let before_pair = tokens[(index + 1)..]
+        .iter()
+        .find(is_significant)
+        .map(|t| t.get_token_type() == TokenType::Pair)
+        .unwrap_or(false);
+
+    tokens[index].get_token_type() == TokenType::Identifier && !after_period && !before_pair
+}
+
+/// Line and column directly after the last character of the given token.
+pub fn end_position(token: &LexerToken) -> (usize, usize) {
+    advance_position(token.get_text(), token.get_line(), token.get_column())
+}
+
+fn advance_position(text: &str, line: usize, column: usize) -> (usize, usize) {
+    text.chars().fold((line, column), step)
+}
+
+fn step((line, column): (usize, usize), c: char) -> (usize, usize) {
+    match c {
+        '\n' => (line + 1, 0),
+        _ => (line, column + 1),
+    }
+}
+
+/// Byte offset into text for a line and column.
+pub fn offset_of(text: &str, line: usize, column: usize) -> usize {
+    let mut position = (0, 0);
+    for (offset, c) in text.char_indices() {
+        if position >= (line, column) {
+            return offset;
+        }
+        position = step(position, c);
+    }
+
+    text.len()
+}
+
+// line and column aren't accessible on CompilerError
+// recover them from its String conversion, "<message> at line <line> col <column>"
+fn error_position(error: CompilerError) -> Option<(usize, usize)> {
+    let message = String::from(error);
+    message
+        .rsplit_once(" at line ")
+        .and_then(|(_, position)| position.split_once(" col "))
+        .and_then(|(line, column)| Some((line.parse().ok()?, column.parse().ok()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokens::{error_position, tokenize, TokenCategory};
+    use garnish_lang::compiler::error::CompilerError;
+
+    #[test]
+    fn categories() {
+        let tokens = tokenize("@Def value { 5 + :sym } @@ comment".to_string());
+        let categories: Vec<TokenCategory> = tokens.iter().map(|t| t.get_category()).collect();
+
+        assert_eq!(
+            categories,
+            vec![
+                TokenCategory::Annotation,
+                TokenCategory::Whitespace,
+                TokenCategory::Identifier,
+                TokenCategory::Whitespace,
+                TokenCategory::Punctuation,
+                TokenCategory::Whitespace,
+                TokenCategory::Number,
+                TokenCategory::Whitespace,
+                TokenCategory::Operator,
+                TokenCategory::Whitespace,
+                TokenCategory::Symbol,
+                TokenCategory::Whitespace,
+                TokenCategory::Punctuation,
+                TokenCategory::Whitespace,
+                TokenCategory::Comment,
+            ]
+        );
+    }
+
+    #[test]
+    fn kind_and_ranges() {
+        let tokens = tokenize("5 +\n\"a\nb\"".to_string());
+        let plus = &tokens[2];
+        let chars = &tokens[4];
+
+        assert_eq!(plus.get_kind(), "PlusSign");
+        assert_eq!((plus.get_start_line(), plus.get_start_column()), (0, 2));
+        assert_eq!((plus.get_end_line(), plus.get_end_column()), (0, 3));
+
+        assert_eq!(chars.get_category(), TokenCategory::String);
+        assert_eq!((chars.get_start_line(), chars.get_start_column()), (1, 0));
+        assert_eq!((chars.get_end_line(), chars.get_end_column()), (2, 2));
+    }
+
+    #[test]
+    fn error_token() {
+        let tokens = tokenize("5 + ? 10".to_string());
+        let last = tokens.last().unwrap();
+
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[2].get_text(), "+");
+        assert_eq!(last.get_category(), TokenCategory::Error);
+        assert_eq!(last.get_text(), "? 10");
+        assert_eq!((last.get_start_line(), last.get_start_column()), (0, 4));
+        assert!(last.get_error().is_some());
+    }
+
+    // fails if the String conversion of CompilerError changes format
+    #[test]
+    fn error_position_format() {
+        assert_eq!(error_position(CompilerError::new("Unknown token at", 3, 7)), Some((3, 7)));
+        assert_eq!(error_position(CompilerError::new("Unknown token", 0, 12)), Some((0, 12)));
+    }
+}